@@ -0,0 +1,37 @@
+//! --format apng：把所有条目的二维码合成一张轮播的动画 PNG
+
+use anyhow::{Context, Result};
+use image::{imageops, DynamicImage, Rgb, RgbImage};
+use std::{fs::File, io::BufWriter, path::Path};
+
+use crate::render::Color;
+
+/// 动画 PNG 的文件名
+pub const FILE_NAME: &str = "slideshow.png";
+
+/// 将每个条目的二维码作为一帧写入无限循环的 APNG，每帧停留 delay_ms 毫秒
+///
+/// 各二维码版本不同尺寸也不同，统一按最大尺寸居中，四周以背景色填充
+pub fn write_apng(path: &Path, frames: &[DynamicImage], delay_ms: u16, bg: Color) -> Result<()> {
+    let width = frames.iter().map(|frame| frame.width()).max().unwrap_or(1);
+    let height = frames.iter().map(|frame| frame.height()).max().unwrap_or(1);
+
+    let file = File::create(path)
+        .with_context(|| format!("无法创建动画文件: {}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 播放次数为 0 表示无限循环
+    encoder.set_animated(frames.len() as u32, 0).context("APNG 编码失败")?;
+    encoder.set_frame_delay(delay_ms, 1000).context("APNG 编码失败")?;
+    let mut writer = encoder.write_header().context("APNG 编码失败")?;
+    for frame in frames {
+        let mut canvas = RgbImage::from_pixel(width, height, Rgb(bg.0));
+        let x = (width - frame.width()) / 2;
+        let y = (height - frame.height()) / 2;
+        imageops::replace(&mut canvas, &frame.to_rgb8(), x as i64, y as i64);
+        writer.write_image_data(canvas.as_raw()).context("APNG 编码失败")?;
+    }
+    writer.finish()
+        .with_context(|| format!("写入动画文件失败: {}", path.display()))
+}
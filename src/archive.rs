@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use std::{fs::{self, File}, io::Write, path::{Path, PathBuf}};
+use zip::{write::SimpleFileOptions, AesMode, CompressionMethod, ZipWriter};
+
+/// 将生成的文件打包为 AES-256 加密的 zip，压缩包内路径相对于输出目录
+pub fn write_encrypted_zip(zip_path: &Path, base_dir: &Path, files: &[PathBuf], password: &str) -> Result<()> {
+    let file = File::create(zip_path)
+        .with_context(|| format!("无法创建压缩包: {}", zip_path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .with_aes_encryption(AesMode::Aes256, password);
+
+    for path in files {
+        let name = path.strip_prefix(base_dir).unwrap_or(path);
+        let data = fs::read(path)
+            .with_context(|| format!("读取待打包文件失败: {}", path.display()))?;
+        zip.start_file(name.to_string_lossy(), options)
+            .with_context(|| format!("写入压缩包失败: {}", name.display()))?;
+        zip.write_all(&data)
+            .with_context(|| format!("写入压缩包失败: {}", name.display()))?;
+    }
+
+    zip.finish()
+        .with_context(|| format!("写入压缩包失败: {}", zip_path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use font8x8::{UnicodeFonts, BASIC_FONTS, LATIN_FONTS};
+use image::{imageops, DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+
+use crate::render::Color;
+
+/// 内置点阵字体的字形边长（像素）
+const GLYPH_SIZE: u32 = 8;
+
+/// 放大 scale 倍后单行文字的高度
+pub fn line_height(scale: u32) -> u32 {
+    GLYPH_SIZE * scale
+}
+
+/// 放大 scale 倍后文字的宽度
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    text.chars().count() as u32 * GLYPH_SIZE * scale
+}
+
+/// 先把文字转写为 ASCII（内置字体只覆盖拉丁字符），再截断使其宽度不超过 max_width，被截断时以 .. 结尾
+pub fn fit_text(text: &str, max_width: u32, scale: u32) -> String {
+    let text = deunicode::deunicode(text);
+    let max_chars = (max_width / (GLYPH_SIZE * scale)) as usize;
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut fitted: String = text.chars().take(max_chars.saturating_sub(2)).collect();
+    fitted.push_str("..");
+    fitted
+}
+
+/// 在画布上绘制一行黑色文字，字体不支持的字符显示为 ?
+pub fn draw_text(canvas: &mut GrayImage, x: u32, y: u32, text: &str, scale: u32) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph = BASIC_FONTS
+            .get(c)
+            .or_else(|| LATIN_FONTS.get(c))
+            .or_else(|| BASIC_FONTS.get('?'))
+            .unwrap_or_default();
+        let origin_x = x + i as u32 * GLYPH_SIZE * scale;
+
+        // 每个字节是一行像素，最低位在最左侧
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_SIZE {
+                if bits & (1 << col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = origin_x + col * scale + dx;
+                        let py = y + row as u32 * scale + dy;
+                        if px < canvas.width() && py < canvas.height() {
+                            canvas.put_pixel(px, py, Luma([0]));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 在二维码图片下方扩展画布，逐行居中绘制说明文字；二维码部分保持原样，不影响扫码
+pub fn add_caption(image: DynamicImage, lines: &[&str], scale: u32, fg: Color, bg: Color) -> DynamicImage {
+    let width = image.width();
+    let gap = GLYPH_SIZE * scale / 2;
+    let line = line_height(scale) + gap;
+    let mut strip = GrayImage::from_pixel(width, line * lines.len() as u32 + gap, Luma([255]));
+    for (n, text) in lines.iter().enumerate() {
+        let text = fit_text(text, width, scale);
+        let x = width.saturating_sub(text_width(&text, scale)) / 2;
+        draw_text(&mut strip, x, n as u32 * line, &text, scale);
+    }
+
+    let height = image.height() + strip.height();
+    match image {
+        // 默认黑白时保持灰度
+        DynamicImage::ImageLuma8(qr) => {
+            let mut canvas = GrayImage::from_pixel(width, height, Luma([255]));
+            imageops::replace(&mut canvas, &qr, 0, 0);
+            imageops::replace(&mut canvas, &strip, 0, qr.height() as i64);
+            DynamicImage::ImageLuma8(canvas)
+        }
+        other => {
+            let qr = other.to_rgb8();
+            let mut canvas = RgbImage::from_pixel(width, height, Rgb(bg.0));
+            imageops::replace(&mut canvas, &qr, 0, 0);
+            for (x, y, pixel) in strip.enumerate_pixels() {
+                if pixel.0[0] == 0 {
+                    canvas.put_pixel(x, qr.height() + y, Rgb(fg.0));
+                }
+            }
+            DynamicImage::ImageRgb8(canvas)
+        }
+    }
+}
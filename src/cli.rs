@@ -0,0 +1,490 @@
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::{input::InputFormat, render::{self, Color, Ecc, RenderOptions}};
+
+/// 将 TOTP 导出 JSON 批量转换为二维码 PNG 和 otpauth 链接
+#[derive(Debug, Parser)]
+#[command(
+    name = "gen-totp-pic",
+    version,
+    about,
+    after_help = concat!(
+        "💡 不带参数直接运行时，默认读取当前目录下的 totp.json\n\n",
+        "退出码:\n",
+        "  0  全部成功\n",
+        "  1  其他错误（如参数校验失败）\n",
+        "  2  命令行参数用法错误\n",
+        "  3  读写文件失败\n",
+        "  4  输入文件解析失败\n",
+        "  5  部分条目失败，其余条目已正常输出\n",
+        "  6  运行时间超过 --time-limit",
+    ),
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub source: InputArgs,
+
+    /// 安静模式：只输出错误信息（写到 stderr）
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// 日志不使用 emoji，改用 [INFO] / [OK] / [WARN] / [ERROR] 等纯文本标签；TERM=dumb 时自动启用
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+
+    /// 失败时向 stderr 输出一行 JSON（kind、exit_code、message 及失败条目），成功时输出 JSON 汇总
+    #[arg(long, global = true)]
+    pub json_errors: bool,
+
+    /// 在日志和错误信息中显示完整 secret（默认只显示前两个字符和长度），仅用于调试
+    #[arg(long, global = true)]
+    pub show_secrets: bool,
+
+    /// 默认配置文件路径，未指定时读取当前目录下的 gen-totp-pic.toml（不存在则忽略）
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// 二维码输出目录
+    #[arg(long, value_name = "PATH", default_value = "qr")]
+    pub output_dir: PathBuf,
+
+    /// 目标二维码文件已存在时跳过该文件，便于续跑中断的大批量任务
+    #[arg(long, conflicts_with = "overwrite")]
+    pub no_clobber: bool,
+
+    /// 增量模式：只为新增的条目生成文件，已存在的二维码和链接保持不动，并汇报新增数量
+    #[arg(long, conflicts_with_all = ["overwrite", "backup", "timestamped"])]
+    pub append: bool,
+
+    /// 覆盖已存在的二维码文件（默认行为）
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// 每次运行写入输出目录下以时间命名的子目录（如 qr/2024-06-01T12-00-00/），保留历史备份
+    #[arg(long)]
+    pub timestamped: bool,
+
+    /// 配合 --timestamped，让 <输出目录>/latest 符号链接指向最新一次运行
+    #[arg(long, requires = "timestamped")]
+    pub latest_symlink: bool,
+
+    /// 输出目录已存在且非空时，先将其重命名为 <目录>.bak-<时间戳> 再重新生成，便于回滚
+    #[arg(long)]
+    pub backup: bool,
+
+    /// 二维码输出格式，可用逗号指定多个（如 png,svg,uri），all 表示 PNG + SVG + uris.txt + index.html
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "png")]
+    pub format: Vec<OutputFormat>,
+
+    /// 二维码每个模块的像素边长，默认 8（与旧版输出尺寸一致）
+    #[arg(long, value_name = "N", default_value_t = render::DEFAULT_SCALE, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub scale: u32,
+
+    /// 在每张二维码图片下方加印服务名称和账户名（仅 PNG / WebP / JPEG）
+    #[arg(long)]
+    pub caption: bool,
+
+    /// 打印每个条目 secret 的指纹（SHA-256 前 8 位），便于比对不同导出中的同一账户
+    #[arg(long)]
+    pub fingerprint: bool,
+
+    /// secret 解码后的最短字节数，低于此值时警告（--strict 时报错）
+    #[arg(long, value_name = "BYTES", default_value_t = gen_totp_pic::MIN_SECRET_LEN)]
+    pub min_secret_bytes: usize,
+
+    /// --format apng 中每帧停留的毫秒数
+    #[arg(long, value_name = "MS", default_value_t = 3000, value_parser = clap::value_parser!(u16).range(1..))]
+    pub frame_delay: u16,
+
+    /// 以 1 位调色板和最高压缩级别重新编码 PNG，减小文件体积（解码结果不变）
+    #[arg(long)]
+    pub optimize: bool,
+
+    /// JPEG 输出质量（1-100），过低时压缩噪点可能导致无法扫码
+    #[arg(long, value_name = "1-100", default_value_t = render::DEFAULT_JPEG_QUALITY, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub quality: u8,
+
+    /// 并行生成二维码的线程数，默认等于 CPU 核心数；为 1 时逐个顺序生成
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub jobs: Option<u32>,
+
+    /// 整次运行的时间上限（秒，不含 --tui 中勾选条目的时间），超过后中止并以退出码 6 结束，同时报告已完成的条目数，便于在 CI 中限制耗时
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "watch")]
+    pub time_limit: Option<u64>,
+
+    /// 二维码纠错等级，等级越高越耐磨损，但码也越密
+    #[arg(long, value_enum, default_value_t = Ecc::M)]
+    pub ecc: Ecc,
+
+    /// 二维码四周留白宽度（模块数），部分扫码器需要足够的留白才能识别
+    #[arg(long, value_name = "MODULES", default_value_t = render::DEFAULT_MARGIN, value_parser = clap::value_parser!(u32).range(0..=40))]
+    pub margin: u32,
+
+    /// 二维码模块颜色（#RRGGBB）
+    #[arg(long, value_name = "#RRGGBB", default_value = "#000000")]
+    pub fg: Color,
+
+    /// 二维码背景颜色（#RRGGBB）
+    #[arg(long, value_name = "#RRGGBB", default_value = "#FFFFFF")]
+    pub bg: Color,
+
+    /// 叠加在每个二维码中心的 logo 图片（PNG），使用时纠错等级固定为 H
+    #[arg(long, value_name = "PATH")]
+    pub logo: Option<PathBuf>,
+
+    /// 输出文件名模板，支持 {label} {username} {index} {issuer}，可用 / 分组到子目录
+    #[arg(long, value_name = "TEMPLATE", default_value = "{label}-{username}")]
+    pub name_template: String,
+
+    /// 按指定字段将二维码分到输出目录下的子目录中，字段为空的条目放入 _ungrouped/
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub group_by: Option<GroupBy>,
+
+    /// 跳过 secret、算法、位数、周期都相同的重复条目，只保留第一个
+    #[arg(long)]
+    pub dedup: bool,
+
+    /// 忽略输入中的 algorithm，所有条目统一使用该算法（SHA1 / SHA256 / SHA512）
+    #[arg(long, value_name = "ALGORITHM", value_parser = parse_algorithm)]
+    pub force_algorithm: Option<String>,
+
+    /// 忽略输入中的 digits，所有条目统一使用该位数（6-8）
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub force_digits: Option<u32>,
+
+    /// 忽略输入中的 period_time，所有条目统一使用该周期（秒）
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub force_period: Option<u64>,
+
+    /// 只处理服务名称或账户名匹配该正则的条目，可用 (?i) 忽略大小写
+    #[arg(long, value_name = "REGEX", value_parser = parse_regex)]
+    pub filter: Option<Regex>,
+
+    /// 生成前按指定字段排序（不区分大小写），默认保持输入顺序
+    #[arg(long, value_enum)]
+    pub sort: Option<SortKey>,
+
+    /// 可复现输出，便于把输出目录纳入版本管理：条目按服务名称、账户名、issuer 等排序，与输入顺序无关（再指定 --sort 时以其为主序）；
+    /// PNG 只写入 IHDR / IDAT / IEND，不含时间戳等元数据块，PDF 使用固定的元数据日期；
+    /// 与带时间戳目录的 --timestamped 及使用随机盐的 --zip 冲突
+    #[arg(long, conflicts_with_all = ["timestamped", "zip"])]
+    pub reproducible: bool,
+
+    /// 只处理筛选、排序后的前 N 个条目，便于调试参数
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub limit: Option<u32>,
+
+    /// 不生成二维码，改为逐条核对参考文件中的验证码（每行 服务名称,验证码，服务名称也可写作 服务名称:账户名）
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    pub verify_codes: Option<PathBuf>,
+
+    /// 不生成二维码，把指定条目（序号从 1 开始，或服务名称 / 服务名称:账户名）复制到剪贴板
+    #[arg(long, value_name = "INDEX_OR_LABEL", conflicts_with_all = ["watch", "verify_codes"])]
+    pub copy: Option<String>,
+
+    /// --copy 复制的内容
+    #[arg(long, value_enum, default_value_t = CopyWhat::Uri, requires = "copy")]
+    pub copy_what: CopyWhat,
+
+    /// codes 子命令与 --verify-codes 按该 Unix 时间戳（秒）计算验证码，默认为当前时间，便于复现结果
+    #[arg(long, value_name = "UNIX_TIME", global = true)]
+    pub at: Option<u64>,
+
+    /// 只重新生成筛选、排序后的第 N 项（从 1 开始，与进度日志中的序号一致），文件名与完整运行时相同
+    #[arg(long, value_name = "INDEX", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["montage", "html", "pdf", "zip"])]
+    pub only: Option<u32>,
+
+    /// 把唯一选中条目（可配合 --only / --filter）的 PNG 字节写到标准输出，日志仍写到标准错误；选中多个条目时报错
+    #[arg(long, conflicts_with_all = ["watch", "montage", "html", "pdf", "zip", "report", "checksums", "dry_run"])]
+    pub stdout: bool,
+
+    /// 生成图片的同时把每个条目的 otpauth 链接打印到标准输出，便于人工核对（默认遮蔽 secret，配合 --show-secrets 显示完整链接）
+    #[arg(long, conflicts_with = "stdout")]
+    pub print_uri: bool,
+
+    /// 在终端界面中浏览条目，可搜索并勾选需要生成的条目，Enter 确认后只生成勾选的条目（界面中不显示 secret）
+    #[arg(long, conflicts_with_all = ["watch", "only", "stdout", "copy", "verify_codes"])]
+    pub tui: bool,
+
+    /// 生成后持续监听输入文件，文件变化时自动重新生成，按 Ctrl-C 退出
+    #[arg(long)]
+    pub watch: bool,
+
+    /// 仅校验输入并构建 TOTP，不创建目录、不写入任何文件
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// 将每个条目的处理结果（含输出路径与 SHA-256）写入 JSON 报告
+    #[arg(long, value_name = "PATH", conflicts_with = "dry_run")]
+    pub report: Option<PathBuf>,
+
+    /// 把生成失败的条目连同失败原因写入 JSON 文件，修正后可直接作为输入重新运行（secret 默认遮蔽，需配合 --show-secrets 保留原文）
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["dry_run", "stdout"])]
+    pub rejects: Option<PathBuf>,
+
+    /// 写入后重新读取并解码每个 PNG，确认内容与 otpauth 链接一致
+    #[arg(long)]
+    pub verify: bool,
+
+    /// 额外以极小的模块尺寸渲染并解码每个二维码，无法识别的条目记为失败，尺寸余量不足的条目单独列出（仍写入正常尺寸的图片）
+    #[arg(long)]
+    pub scan_test: bool,
+
+    /// 额外生成一张包含所有二维码的拼图 contact-sheet.png，便于打印备份
+    #[arg(long)]
+    pub montage: bool,
+
+    /// 额外生成自包含的 index.html（二维码以 base64 内嵌），便于在浏览器中逐个扫码
+    #[arg(long)]
+    pub html: bool,
+
+    /// 额外生成可打印的 PDF：每页一个二维码，附服务名、账户名和 otpauth 链接
+    #[arg(long, value_name = "PATH")]
+    pub pdf: Option<PathBuf>,
+
+    /// 拼图每行的二维码数量
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub columns: u32,
+
+    /// 将生成的二维码打包为 AES 加密的 zip 文件
+    #[arg(long, value_name = "PATH")]
+    pub zip: Option<PathBuf>,
+
+    /// zip 加密密码，也可通过环境变量 GEN_TOTP_PIC_ZIP_PASSWORD 提供
+    #[arg(long, env = "GEN_TOTP_PIC_ZIP_PASSWORD", hide_env_values = true, requires = "zip")]
+    pub password: Option<String>,
+
+    /// 打包完成后删除输出目录中未加密的二维码、链接文件和 uris.txt
+    #[arg(long, requires = "zip")]
+    pub remove_loose: bool,
+
+    /// 在输出目录写入 SHA256SUMS，列出本次生成的全部文件，可用 sha256sum -c 校验
+    #[arg(long, conflicts_with_all = ["dry_run", "remove_loose"])]
+    pub checksums: bool,
+}
+
+impl Cli {
+    /// 展开 --format all 并去掉重复的格式；all 同时开启 --html
+    pub fn expand_formats(&mut self) -> Result<()> {
+        let mut formats = Vec::new();
+        for format in std::mem::take(&mut self.format) {
+            let expanded = if format == OutputFormat::All {
+                self.html = true;
+                vec![OutputFormat::Png, OutputFormat::Svg, OutputFormat::Uri]
+            } else {
+                vec![format]
+            };
+            for format in expanded {
+                if !formats.contains(&format) {
+                    formats.push(format);
+                }
+            }
+        }
+        if formats.contains(&OutputFormat::Migration) && formats.len() > 1 {
+            return Err(anyhow::anyhow!("migration 格式不能与其他格式同时使用"));
+        }
+        self.format = formats;
+        Ok(())
+    }
+
+    /// 是否选择了指定的输出格式
+    pub fn has_format(&self, format: OutputFormat) -> bool {
+        self.format.contains(&format)
+    }
+
+    /// 是否保留已存在的输出文件（--no-clobber 或 --append）
+    pub fn keep_existing(&self) -> bool {
+        self.no_clobber || self.append
+    }
+
+    /// 是否会为每个条目生成二维码图片文件
+    pub fn has_images(&self) -> bool {
+        self.format.iter().any(|format| format.has_image())
+    }
+
+    /// 根据命令行参数构建渲染参数，指定 logo 时读取图片并将纠错等级提升为 H
+    pub fn render_options(&self) -> Result<RenderOptions> {
+        let logo = match &self.logo {
+            Some(path) => Some(
+                image::open(path)
+                    .with_context(|| format!("无法读取 logo 图片: {}", path.display()))?
+                    .to_rgba8(),
+            ),
+            None => None,
+        };
+        Ok(RenderOptions {
+            scale: self.scale,
+            ecc: if logo.is_some() { Ecc::H } else { self.ecc },
+            margin: self.margin,
+            fg: self.fg,
+            bg: self.bg,
+            logo,
+        })
+    }
+}
+
+/// 解析 --force-algorithm，接受与输入文件相同的写法并规范化为 SHA1 / SHA256 / SHA512
+fn parse_algorithm(name: &str) -> Result<String, String> {
+    match gen_totp_pic::normalize_algorithm(name).as_str() {
+        algorithm @ ("SHA1" | "SHA256" | "SHA512") => Ok(algorithm.to_string()),
+        _ => Err(format!("不支持的算法: {}，仅支持 SHA1/SHA256/SHA512", name)),
+    }
+}
+
+/// 解析 --filter 的正则表达式
+fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("无效的正则表达式: {}", e))
+}
+
+/// 输入来源参数，生成二维码与各子命令共用
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// 输入文件路径（JSON / CSV / YAML），可指定多个或使用通配符，"-" 表示从标准输入读取，
+    /// 也可以是 https:// 地址
+    #[arg(value_name = "输入文件路径", default_value = "totp.json")]
+    pub input: Vec<PathBuf>,
+
+    /// 输入文件格式，未指定时根据扩展名推断
+    #[arg(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+
+    /// total_entries 不一致、导出时间异常时直接报错；secret 过短或名称含有冒号的条目记为失败，其余条目照常生成
+    #[arg(long)]
+    pub strict: bool,
+
+    /// 网络请求超时时间（秒）
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// 允许 secret 的 file: 引用使用绝对路径或 .. 读取输入文件所在目录之外的文件
+    #[arg(long)]
+    pub allow_absolute_secrets: bool,
+
+    /// 允许通过明文 http:// 读取输入
+    #[arg(long)]
+    pub insecure: bool,
+}
+
+/// 子命令
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 从 otpauth:// 链接列表（每行一条）重新生成 JSON 导出文件
+    ImportUris {
+        /// otpauth 链接文本文件路径
+        #[arg(value_name = "URI文件路径")]
+        input: PathBuf,
+
+        /// 生成的 JSON 文件路径
+        #[arg(short, long, value_name = "PATH")]
+        output: PathBuf,
+    },
+
+    /// 以表格列出输入中的条目（secret 已脱敏），不生成任何文件
+    List {
+        #[command(flatten)]
+        source: InputArgs,
+    },
+
+    /// 直接在终端打印每个条目当前的验证码
+    Codes {
+        #[command(flatten)]
+        source: InputArgs,
+
+        /// 每秒原地刷新验证码和倒计时，直到 Ctrl-C
+        #[arg(long = "loop")]
+        repeat: bool,
+    },
+}
+
+/// 条目排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// 按服务名称排序，相同时按账户名
+    Label,
+    /// 按账户名排序，相同时按服务名称
+    Username,
+}
+
+/// --copy 复制的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CopyWhat {
+    /// otpauth 链接（包含 secret）
+    Uri,
+    /// 当前验证码
+    Code,
+}
+
+/// 输出分组字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// 按服务提供方分组，未设置 issuer 时使用服务名称
+    Issuer,
+}
+
+/// 二维码输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// PNG 位图
+    Png,
+    /// SVG 矢量图
+    Svg,
+    /// 无损 WebP 位图，体积比 PNG 更小
+    Webp,
+    /// 有损 JPEG 位图，质量由 --quality 控制
+    Jpeg,
+    /// 将完整 otpauth 链接汇总写入 uris.txt
+    Uri,
+    /// 直接在终端打印字符画二维码，不写入任何文件
+    Terminal,
+    /// 将所有条目打包为 Google Authenticator 可导入的 otpauth-migration 二维码 migration-N.png
+    Migration,
+    /// 所有条目的二维码依次作为帧，合成一张轮播的动画 PNG（slideshow.png）
+    Apng,
+    /// 一次输出 PNG、SVG、uris.txt 和 index.html 画廊
+    All,
+}
+
+impl OutputFormat {
+    /// 是否为每个条目单独生成二维码图片文件
+    pub fn has_image(self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::Svg | OutputFormat::Webp | OutputFormat::Jpeg)
+    }
+
+    /// 是否为可以解码校验的位图
+    pub fn is_raster(self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::Webp | OutputFormat::Jpeg | OutputFormat::Migration)
+    }
+
+    /// 汇总日志中显示的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "PNG",
+            OutputFormat::Svg => "SVG",
+            OutputFormat::Webp => "WebP",
+            OutputFormat::Jpeg => "JPEG",
+            OutputFormat::Uri => "URI",
+            OutputFormat::Terminal => "终端",
+            OutputFormat::Migration => "迁移二维码",
+            OutputFormat::Apng => "APNG",
+            OutputFormat::All => "全部",
+        }
+    }
+
+    /// 输出文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png | OutputFormat::Migration | OutputFormat::Apng => "png",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Uri | OutputFormat::Terminal | OutputFormat::All => "txt",
+        }
+    }
+}
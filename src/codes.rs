@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use log::{error, info};
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use gen_totp_pic::{build_totp, hotp_code, OtpKind, TotpEntry, TotpExport};
+
+use crate::exit::PartialFailure;
+
+/// 打印每个条目在 at 时刻（默认当前时间）的验证码及剩余有效秒数；repeat 时每秒原地刷新，直到 Ctrl-C
+pub fn print_codes(export: &TotpExport, repeat: bool, at: Option<u64>) -> Result<()> {
+    if repeat && at.is_some() {
+        return Err(anyhow::anyhow!("--loop 按当前时间刷新，不能与 --at 同时使用"));
+    }
+    let (lines, failed) = code_lines(export, at.unwrap_or_else(unix_time), true);
+    for line in &lines {
+        println!("{}", line);
+    }
+    if repeat {
+        refresh(export, lines.len())?;
+    }
+
+    if failed > 0 {
+        return Err(PartialFailure::new(format!("{} 个条目无法生成验证码", failed)).into());
+    }
+    Ok(())
+}
+
+/// 按参考文件逐条核对验证码，TOTP 在 at 时刻（默认当前时间）计算并允许条目的 skew 窗口
+///
+/// 参考文件每行为 `服务名称,验证码`，服务名称也可写作 `服务名称:账户名` 以区分同名条目；
+/// 空行和 # 开头的行会被忽略
+pub fn verify_codes(export: &TotpExport, reference: &Path, at: Option<u64>) -> Result<()> {
+    let now = at.unwrap_or_else(unix_time);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .from_path(reference)
+        .with_context(|| format!("无法读取参考文件: {}", reference.display()))?;
+
+    let (mut matched, mut failed) = (0, 0);
+    for (index, row) in reader.records().enumerate() {
+        let row = row.with_context(|| format!("参考文件第 {} 行解析失败", index + 1))?;
+        let (Some(label), Some(expected)) = (row.get(0), row.get(1)) else {
+            return Err(anyhow::anyhow!("参考文件第 {} 行应为 服务名称,验证码", index + 1));
+        };
+        let candidates: Vec<&TotpEntry> = export.entries
+            .iter()
+            .filter(|entry| entry.label_name == label || format!("{}:{}", entry.label_name, entry.username) == label)
+            .collect();
+        if candidates.is_empty() {
+            error!("❓ {}: 输入中没有对应的条目", label);
+            failed += 1;
+            continue;
+        }
+
+        // 同名条目只要有一个验证码一致即视为通过
+        let mut actual = Vec::new();
+        let mut ok = false;
+        for entry in candidates {
+            let result = if entry.kind == OtpKind::Hotp {
+                hotp_code(entry).map(|code| (code == expected, code))
+            } else {
+                build_totp(entry)
+                    .map_err(anyhow::Error::from)
+                    .map(|totp| (totp.check(expected, now), totp.generate(now)))
+            };
+            match result {
+                Ok((true, _)) => ok = true,
+                Ok((false, code)) => actual.push(code),
+                Err(e) => actual.push(format!("无法生成: {:#}", e)),
+            }
+        }
+        if ok {
+            info!("✅ {}: {}", label, expected);
+            matched += 1;
+        } else {
+            error!("❌ {}: 期望 {}，实际 {}", label, expected, actual.join(" / "));
+            failed += 1;
+        }
+    }
+
+    info!("📊 核对时间 {}：一致 {} 项，不一致 {} 项", now, matched, failed);
+    if failed > 0 {
+        return Err(PartialFailure::new(format!("{} 项验证码与参考文件不一致", failed)).into());
+    }
+    Ok(())
+}
+
+/// 生成 now 时刻每个条目的输出行，返回输出行与失败数；report 为 true 时打印失败原因
+fn code_lines(export: &TotpExport, now: u64, report: bool) -> (Vec<String>, usize) {
+    let mut lines = Vec::new();
+    let mut failed = 0;
+    for entry in &export.entries {
+        let line = if entry.kind == OtpKind::Hotp {
+            hotp_code(entry).map(|code| format!("{} ({}): {}  🔢 计数器 {}",
+                                                entry.label_name, entry.username, code, entry.counter.unwrap_or_default()))
+        } else {
+            // 每个条目按各自的 digits 与 period_time 计算
+            build_totp(entry).map_err(anyhow::Error::from).map(|totp| {
+                let remaining = totp.step - now % totp.step;
+                format!("{} ({}): {}  ⏳ {} 秒后刷新", entry.label_name, entry.username, totp.generate(now), remaining)
+            })
+        };
+        match line {
+            Ok(line) => lines.push(line),
+            Err(e) => {
+                if report {
+                    error!("❌ {} ({}): {:#}", entry.label_name, entry.username, e);
+                }
+                failed += 1;
+            }
+        }
+    }
+    (lines, failed)
+}
+
+/// 每到整秒时把光标移回列表开头，清屏后重新打印
+fn refresh(export: &TotpExport, mut height: usize) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = sender.send(());
+    })
+    .context("无法注册 Ctrl-C 处理")?;
+
+    let mut stdout = io::stdout();
+    loop {
+        match receiver.recv_timeout(until_next_second()) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return Ok(()),
+        }
+        let (lines, _) = code_lines(export, unix_time(), false);
+        let mut out = String::new();
+        if height > 0 {
+            out.push_str(&format!("\x1b[{}A", height));
+        }
+        out.push_str("\x1b[J");
+        for line in &lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        stdout.write_all(out.as_bytes())
+            .and_then(|()| stdout.flush())
+            .context("无法写入标准输出")?;
+        height = lines.len();
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// 距离下一个整秒的时长，让倒计时与系统时钟同步跳变
+fn until_next_second() -> Duration {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Duration::from_secs(1) - Duration::from_nanos(elapsed.subsec_nanos() as u64)
+}
@@ -0,0 +1,99 @@
+//! gen-totp-pic.toml 默认配置，命令行参数优先于配置文件
+
+use anyhow::{Context, Result};
+use clap::{ArgMatches, ValueEnum, parser::ValueSource};
+use log::info;
+use serde::Deserialize;
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::{cli::{Cli, OutputFormat}, render::Color};
+
+/// 未指定 --config 时在当前目录查找的配置文件
+pub const DEFAULT_PATH: &str = "gen-totp-pic.toml";
+
+/// 配置文件内容，键名与对应的命令行参数一致
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    output_dir: Option<PathBuf>,
+    format: Option<String>,
+    scale: Option<u32>,
+    fg: Option<String>,
+    bg: Option<String>,
+    name_template: Option<String>,
+}
+
+/// 读取配置文件，并把其中的值填入命令行未显式指定的参数
+///
+/// 默认配置文件不存在时直接跳过；通过 --config 指定的文件必须存在
+pub fn apply(cli: &mut Cli, matches: &ArgMatches) -> Result<()> {
+    let (path, explicit) = match &cli.config {
+        Some(path) => (path.clone(), true),
+        None => (PathBuf::from(DEFAULT_PATH), false),
+    };
+    let Some(config) = load(&path, explicit)? else {
+        return Ok(());
+    };
+
+    // 只有取默认值的参数才会被配置文件覆盖
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if let Some(output_dir) = config.output_dir
+        && !from_cli("output_dir")
+    {
+        cli.output_dir = output_dir;
+    }
+    if let Some(format) = config.format
+        && !from_cli("format")
+    {
+        cli.format = format
+            .split(',')
+            .map(|name| OutputFormat::from_str(name.trim(), true).map_err(|_| anyhow::anyhow!(
+                "配置项 format 无效: {}，可选值为 png / svg / webp / jpeg / uri / terminal / migration / apng / all",
+                name
+            )))
+            .collect::<Result<_>>()?;
+    }
+    if let Some(scale) = config.scale
+        && !from_cli("scale")
+    {
+        if !(1..=100).contains(&scale) {
+            return Err(anyhow::anyhow!("配置项 scale 应在 1 到 100 之间: {}", scale));
+        }
+        cli.scale = scale;
+    }
+    if let Some(fg) = config.fg
+        && !from_cli("fg")
+    {
+        cli.fg = parse_color("fg", &fg)?;
+    }
+    if let Some(bg) = config.bg
+        && !from_cli("bg")
+    {
+        cli.bg = parse_color("bg", &bg)?;
+    }
+    if let Some(name_template) = config.name_template
+        && !from_cli("name_template")
+    {
+        cli.name_template = name_template;
+    }
+    Ok(())
+}
+
+/// 读取并解析配置文件，文件不存在且不是显式指定时返回 None
+fn load(path: &Path, explicit: bool) -> Result<Option<Config>> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("无法读取配置文件: {}", path.display())),
+    };
+    info!("⚙️  读取配置文件: {}", path.display());
+    let config = toml::from_str(&data)
+        .with_context(|| format!("配置文件解析失败: {}", path.display()))?;
+    Ok(Some(config))
+}
+
+fn parse_color(key: &str, value: &str) -> Result<Color> {
+    value
+        .parse()
+        .map_err(|e| anyhow::anyhow!("配置项 {} 无效: {}", key, e))
+}
@@ -0,0 +1,63 @@
+//! --copy：把单个条目的 otpauth 链接或当前验证码复制到剪贴板
+
+use anyhow::{Context, Result};
+use log::info;
+use zeroize::Zeroizing;
+
+use gen_totp_pic::{build_hotp_url, build_totp, hotp_code, OtpKind, TotpEntry, TotpExport};
+
+use crate::cli::CopyWhat;
+
+/// 按序号（从 1 开始）或服务名称选出条目并复制，提示信息中不包含 secret
+pub fn copy_entry(export: &TotpExport, selector: &str, what: CopyWhat) -> Result<()> {
+    let entry = select(export, selector)?;
+    let text = Zeroizing::new(match (what, entry.kind) {
+        (CopyWhat::Uri, OtpKind::Totp) => build_totp(entry)?.get_url(),
+        (CopyWhat::Uri, OtpKind::Hotp) => build_hotp_url(entry)?,
+        (CopyWhat::Code, OtpKind::Totp) => build_totp(entry)?.generate_current()?,
+        (CopyWhat::Code, OtpKind::Hotp) => hotp_code(entry)?,
+    });
+
+    let mut clipboard = arboard::Clipboard::new().context("无法访问剪贴板")?;
+    let what = match what {
+        CopyWhat::Uri => "otpauth 链接",
+        CopyWhat::Code => "当前验证码",
+    };
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::SetExtLinux;
+        // X11 / Wayland 下剪贴板内容由本进程持有，退出后即失效
+        info!("📋 已将 {} ({}) 的{}复制到剪贴板，粘贴完成后按 Ctrl-C 退出", entry.label_name, entry.username, what);
+        clipboard.set().wait().text(text.as_str()).context("写入剪贴板失败")?;
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        clipboard.set_text(text.as_str()).context("写入剪贴板失败")?;
+        info!("📋 已将 {} ({}) 的{}复制到剪贴板", entry.label_name, entry.username, what);
+    }
+    Ok(())
+}
+
+/// 纯数字按序号选择，否则按服务名称或 服务名称:账户名 匹配，匹配到多个时报错
+fn select<'a>(export: &'a TotpExport, selector: &str) -> Result<&'a TotpEntry> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return index
+            .checked_sub(1)
+            .and_then(|i| export.entries.get(i))
+            .ok_or_else(|| anyhow::anyhow!("序号 {} 超出范围，共 {} 个条目", index, export.entries.len()));
+    }
+    let matches: Vec<&TotpEntry> = export.entries
+        .iter()
+        .filter(|entry| entry.label_name == selector || format!("{}:{}", entry.label_name, entry.username) == selector)
+        .collect();
+    match matches.as_slice() {
+        [entry] => Ok(entry),
+        [] => Err(anyhow::anyhow!("没有找到服务名称为 {} 的条目", selector)),
+        _ => Err(anyhow::anyhow!(
+            "服务名称 {} 匹配到 {} 个条目，请改用序号或 服务名称:账户名（{}）",
+            selector,
+            matches.len(),
+            matches.iter().map(|entry| format!("{}:{}", entry.label_name, entry.username)).collect::<Vec<_>>().join("、")
+        )),
+    }
+}
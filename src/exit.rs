@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::{fmt, io, process::ExitCode};
+
+/// 其他错误（参数校验失败等）
+pub const GENERAL: u8 = 1;
+// 2 由 clap 在命令行参数用法错误时使用
+/// 读写文件或网络请求失败
+pub const IO: u8 = 3;
+/// 输入文件解析失败
+pub const PARSE: u8 = 4;
+/// 部分条目处理失败，其余条目已正常输出
+pub const PARTIAL: u8 = 5;
+/// 运行时间超过 --time-limit
+pub const TIMEOUT: u8 = 6;
+
+/// 部分条目失败的标记错误，用于区分整体失败与部分失败
+#[derive(Debug)]
+pub struct PartialFailure {
+    pub message: String,
+    /// 失败的条目，供 --json-errors 输出
+    pub entries: Vec<FailedEntry>,
+}
+
+impl PartialFailure {
+    pub fn new(message: String) -> Self {
+        PartialFailure { message, entries: Vec::new() }
+    }
+}
+
+/// 单个失败条目
+#[derive(Debug, Serialize)]
+pub struct FailedEntry {
+    /// 从 1 开始的序号，与日志一致
+    pub entry_index: usize,
+    pub label: String,
+    pub username: String,
+    pub message: String,
+}
+
+impl fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+/// 运行超时，由 --time-limit 的看门狗报告
+#[derive(Debug)]
+pub struct Timeout {
+    pub limit: u64,
+    /// 超时前已生成完毕的条目数
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "运行时间超过 --time-limit {} 秒，已中止（已完成 {}/{} 个条目）", self.limit, self.completed, self.total)
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// 向 stderr 输出错误：json 为 true 时输出单行 JSON，否则输出完整的错误链
+pub fn print(error: &anyhow::Error, json: bool) {
+    if json {
+        print_json(error);
+    } else {
+        eprintln!("Error: {:?}", error);
+    }
+}
+
+/// 根据错误链中的底层错误类型选择退出码
+pub fn code_for(error: &anyhow::Error) -> ExitCode {
+    ExitCode::from(category(error))
+}
+
+/// --json-errors 时输出到 stderr 的错误对象
+#[derive(Serialize)]
+struct JsonError<'a> {
+    kind: &'static str,
+    exit_code: u8,
+    message: String,
+    #[serde(skip_serializing_if = "<[FailedEntry]>::is_empty")]
+    entries: &'a [FailedEntry],
+}
+
+/// 以单行 JSON 向 stderr 输出错误，kind 与退出码对应
+fn print_json(error: &anyhow::Error) {
+    let code = category(error);
+    let kind = match code {
+        TIMEOUT => "timeout",
+        PARTIAL => "partial",
+        PARSE => "parse",
+        IO => "io",
+        _ => "general",
+    };
+    let entries = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<PartialFailure>())
+        .map_or(&[][..], |partial| partial.entries.as_slice());
+    let json = JsonError { kind, exit_code: code, message: format!("{:#}", error), entries };
+    eprintln!("{}", serde_json::to_string(&json).expect("错误信息可以序列化"));
+}
+
+fn category(error: &anyhow::Error) -> u8 {
+    error
+        .chain()
+        .find_map(|cause| {
+            if cause.is::<Timeout>() {
+                Some(TIMEOUT)
+            } else if cause.is::<PartialFailure>() {
+                Some(PARTIAL)
+            } else if cause.is::<crate::input::EmptyInput>()
+                || cause.is::<serde_json::Error>()
+                || cause.is::<serde_yaml::Error>()
+                || cause.is::<csv::Error>()
+                || cause.is::<prost::DecodeError>()
+                || cause.is::<base64::DecodeError>()
+                || cause.is::<url::ParseError>()
+            {
+                Some(PARSE)
+            } else if cause.is::<io::Error>() || cause.is::<reqwest::Error>() {
+                Some(IO)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(GENERAL)
+}
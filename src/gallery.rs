@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{fs, path::{Path, PathBuf}};
+
+/// 画廊中的一项：二维码图片及其说明
+pub struct Item {
+    pub image_path: PathBuf,
+    pub label: String,
+    pub username: String,
+}
+
+/// 页面样式：自适应网格，窄屏下自动减少列数
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2rem; background: #f5f5f5; }
+h1 { font-size: 1.4rem; }
+.grid { display: grid; grid-template-columns: repeat(auto-fill, minmax(220px, 1fr)); gap: 1.5rem; }
+figure { margin: 0; padding: 1rem; background: #fff; border-radius: 8px; text-align: center; }
+img { width: 100%; height: auto; image-rendering: pixelated; }
+figcaption { margin-top: 0.5rem; word-break: break-all; }
+.username { color: #666; font-size: 0.9rem; }
+";
+
+/// 将所有二维码以 base64 data URI 内嵌到单个 HTML 文件中，无需附带图片即可打开
+pub fn write_gallery(path: &Path, items: &[Item]) -> Result<()> {
+    let mut figures = String::new();
+    for item in items {
+        let bytes = fs::read(&item.image_path)
+            .with_context(|| format!("读取二维码文件失败: {:?}", item.image_path))?;
+        let mime = match item.image_path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => "image/svg+xml",
+            Some("webp") => "image/webp",
+            Some("jpg") => "image/jpeg",
+            _ => "image/png",
+        };
+        figures.push_str(&format!(
+            concat!(
+                "<figure>\n",
+                "<img src=\"data:{mime};base64,{data}\" alt=\"{label}\">\n",
+                "<figcaption><div>{label}</div><div class=\"username\">{username}</div></figcaption>\n",
+                "</figure>\n"
+            ),
+            mime = mime,
+            data = STANDARD.encode(bytes),
+            label = escape(&item.label),
+            username = escape(&item.username),
+        ));
+    }
+
+    let html = format!(
+        concat!(
+            "<!DOCTYPE html>\n",
+            "<html lang=\"zh-CN\">\n",
+            "<head>\n",
+            "<meta charset=\"utf-8\">\n",
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n",
+            "<title>TOTP 二维码</title>\n",
+            "<style>\n{style}</style>\n",
+            "</head>\n",
+            "<body>\n",
+            "<h1>TOTP 二维码（共 {count} 项）</h1>\n",
+            "<div class=\"grid\">\n{figures}</div>\n",
+            "</body>\n",
+            "</html>\n"
+        ),
+        style = STYLE,
+        count = items.len(),
+        figures = figures,
+    );
+    fs::write(path, html)
+        .with_context(|| format!("写入画廊页面失败: {:?}", path))
+}
+
+/// 转义 HTML 特殊字符
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,1232 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use rayon::prelude::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use chrono::Local;
+use image::DynamicImage;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal, Write},
+    mem,
+    path::{Path, PathBuf},
+    process,
+    sync::{atomic::{AtomicUsize, Ordering}, mpsc::{self, RecvTimeoutError}, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+use terminal_size::Width;
+use totp_rs::TOTP;
+use zeroize::{Zeroize, Zeroizing};
+
+use gen_totp_pic::{build_hotp_url, build_totp, check_label, check_secret_len, decode_secret, normalize_algorithm, redact_url, sanitize, secret_fingerprint, OtpKind, TotpEntry};
+
+use crate::{
+    apng, archive, caption,
+    exit::{self, FailedEntry, PartialFailure},
+    cli::{Cli, GroupBy, OutputFormat, SortKey},
+    gallery, input, migration, montage, pdf,
+    render::{self, Ecc, RenderOptions},
+    report::{self, EntryReport, ImageReport, Outcome},
+    tui, verify,
+};
+
+/// 条目在 --force-* 覆盖参数之前的字段，用于写入 --rejects
+type Snapshot = serde_json::Map<String, serde_json::Value>;
+
+/// 读取输入并批量生成二维码与链接文件
+pub fn run(cli: &Cli) -> Result<()> {
+    let mut started = Instant::now();
+    // 1. 读取并解析输入文件
+    let mut export = input::load(&cli.source)?;
+    // 导入时跳过的非 TOTP 条目同样计入总数
+    let loaded = export.entries.len() + export.skipped;
+    
+    match &export.export_time {
+        Some(time) => info!("📊 导出时间: {}", input::describe_export_time(time)),
+        None => info!("📊 导出时间: 未知"),
+    }
+    match export.total_entries {
+        Some(total) => info!("📊 总条目数: {}", total),
+        None => info!("📊 总条目数: 未声明"),
+    }
+    info!("📊 实际条目数: {}", export.entries.len());
+
+    let duplicates = if cli.dedup { dedup_entries(&mut export.entries) } else { 0 };
+    if duplicates > 0 {
+        info!("📊 去重后条目数: {}", export.entries.len());
+    }
+    if let Some(filter) = &cli.filter {
+        let total = export.entries.len();
+        export.entries.retain(|entry| filter.is_match(&entry.label_name) || filter.is_match(&entry.username));
+        info!("🔎 匹配 {} 的条目: {}/{}", filter, export.entries.len(), total);
+        if export.entries.is_empty() {
+            warn!("⚠️  没有条目匹配筛选条件");
+            return Ok(());
+        }
+    }
+    // 先排成与输入顺序无关的全序，--sort 为稳定排序，相同键的条目仍保持该顺序
+    if cli.reproducible {
+        sort_reproducible(&mut export.entries);
+    }
+    if let Some(key) = cli.sort {
+        sort_entries(&mut export.entries, key);
+    }
+    if let Some(limit) = cli.limit
+        && export.entries.len() > limit as usize
+    {
+        info!("✂️  已限制为前 {}/{} 个条目", limit, export.entries.len());
+        export.entries.truncate(limit as usize);
+    }
+    // 每个条目在 --tui 勾选前的序号，{index} 按它展开，文件名与不勾选时一致
+    let mut ordinals: Vec<usize> = (0..export.entries.len()).collect();
+    if cli.tui && !export.entries.is_empty() {
+        let picking = Instant::now();
+        let Some(selected) = tui::select(&export.entries)? else {
+            info!("🚪 已取消，未生成任何文件");
+            return Ok(());
+        };
+        // 在界面中勾选的时间不计入 --time-limit
+        started += picking.elapsed();
+        let total = export.entries.len();
+        let mut index = 0;
+        export.entries.retain(|_| {
+            index += 1;
+            selected.contains(&(index - 1))
+        });
+        info!("☑️  已勾选 {}/{} 个条目", export.entries.len(), total);
+        ordinals = selected;
+    }
+    if let Some(only) = cli.only
+        && only as usize > export.entries.len()
+    {
+        return Err(anyhow::anyhow!("--only {} 超出范围，共 {} 个条目", only, export.entries.len()));
+    }
+    
+    if export.entries.is_empty() {
+        warn!("⚠️  没有找到任何 TOTP 条目");
+        return Ok(());
+    }
+
+    // --force-* 会覆盖条目参数，先记下输入中的原始字段，写入失败条目时使用
+    let mut snapshots: Vec<Snapshot> = match cli.rejects {
+        Some(_) => export.entries.iter().map(report::Reject::snapshot).collect(),
+        None => Vec::new(),
+    };
+    force_parameters(cli, &mut export.entries);
+
+    validate_template(&cli.name_template)?;
+    let raster = cli.format.iter().any(|format| format.is_raster());
+    if cli.verify && !raster {
+        return Err(anyhow::anyhow!("--verify 目前仅支持 PNG / WebP / JPEG 输出"));
+    }
+    // APNG 的每一帧同样是位图，支持 logo 与说明文字
+    let bitmap = raster || cli.has_format(OutputFormat::Apng);
+    if cli.logo.is_some() && (!bitmap || cli.has_format(OutputFormat::Svg)) {
+        return Err(anyhow::anyhow!("--logo 目前仅支持 PNG / WebP / JPEG 输出"));
+    }
+    if cli.caption && (!bitmap || cli.has_format(OutputFormat::Svg)) {
+        return Err(anyhow::anyhow!("--caption 目前仅支持 PNG / WebP / JPEG 输出"));
+    }
+    if cli.has_format(OutputFormat::Jpeg) && cli.quality < render::MIN_JPEG_QUALITY {
+        warn!("⚠️  JPEG 质量 {} 较低，有损压缩的噪点可能导致二维码无法被识别", cli.quality);
+    }
+    let render_options = cli.render_options()?;
+    if cli.logo.is_some() && cli.ecc != Ecc::H {
+        info!("🛡️  已指定 logo，纠错等级自动提升为 H");
+    }
+    let contrast = cli.fg.contrast(cli.bg);
+    if contrast < render::MIN_CONTRAST {
+        warn!("⚠️  前景色 {} 与背景色 {} 的对比度仅为 {:.1}:1，生成的二维码可能无法被识别", cli.fg, cli.bg, contrast);
+    }
+    if cli.html && !cli.has_images() {
+        return Err(anyhow::anyhow!("--html 不支持 uri / terminal / migration 格式"));
+    }
+    if cli.zip.is_some() {
+        if !cli.has_images() {
+            return Err(anyhow::anyhow!("--zip 不支持 uri / terminal / migration 格式"));
+        }
+        if cli.password.as_deref().is_none_or(str::is_empty) {
+            return Err(anyhow::anyhow!(
+                "使用 --zip 时必须通过 --password 或环境变量 GEN_TOTP_PIC_ZIP_PASSWORD 提供密码"
+            ));
+        }
+    }
+    // 看门狗在分派到任何输出方式之前启动，--stdout 与 migration 格式同样受 --time-limit 限制
+    let completed = Arc::new(AtomicUsize::new(0));
+    let selected = if cli.only.is_some() { 1 } else { export.entries.len() };
+    let _watchdog = cli.time_limit.map(|limit| start_watchdog(cli, started, limit, Arc::clone(&completed), selected));
+
+    if cli.stdout {
+        return write_stdout(cli, &export.entries, &render_options);
+    }
+
+    // 2. 确定并创建输出目录
+    let output_dir = &resolve_output_dir(cli);
+    if output_dir.is_file() {
+        return Err(anyhow::anyhow!(
+            "输出路径已存在但不是目录: {}",
+            output_dir.display()
+        ));
+    }
+    if cli.backup && !cli.dry_run {
+        backup_output_dir(output_dir)?;
+    }
+
+    if cli.has_format(OutputFormat::Migration) {
+        if cli.montage || cli.pdf.is_some() || cli.report.is_some() {
+            return Err(anyhow::anyhow!("migration 格式不支持 --montage / --pdf / --report"));
+        }
+        return write_migration(cli, &export.entries, snapshots, &render_options, output_dir);
+    }
+
+    // 只输出到终端时，仅在生成拼图时才需要输出目录
+    let terminal_only = cli.format == [OutputFormat::Terminal];
+    if !cli.dry_run && (!terminal_only || cli.montage) {
+        create_output_dir(cli, output_dir)?;
+        if cli.has_images() {
+            let links_dir = output_dir.join(LINKS_DIR);
+            fs::create_dir_all(&links_dir)
+                .with_context(|| format!("无法创建链接目录: {}", links_dir.display()))?;
+        }
+    }
+    
+    // 3. 先按顺序确定每一项的文件名，重名时追加 -1、-2 等后缀
+    let mut seen = HashSet::new();
+    let mut filenames: Vec<String> = export.entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut base = expand_template(&cli.name_template, entry, ordinals[index]);
+            if let Some(GroupBy::Issuer) = cli.group_by {
+                base = format!("{}/{}", group_dir(entry.issuer()), base);
+            }
+            let filename = unique_filename(&base, &mut seen);
+            let selected = cli.only.is_none_or(|only| only as usize == index + 1);
+            if filename != base && cli.has_images() && selected {
+                warn!("⚠️  文件名冲突: {} ({}) 的 {} 已被占用，改用 {}",
+                         entry.label_name, entry.username, base, filename);
+            }
+            filename
+        })
+        .collect();
+
+    // --only 在确定文件名之后再选取，保证文件名与完整运行时一致
+    if let Some(only) = cli.only {
+        let index = only as usize - 1;
+        let entry = export.entries.swap_remove(index);
+        info!("🎯 只处理第 {} 项: {} ({})", only, entry.label_name, entry.username);
+        export.entries = vec![entry];
+        filenames = vec![filenames.swap_remove(index)];
+        if !snapshots.is_empty() {
+            snapshots = vec![snapshots.swap_remove(index)];
+        }
+    }
+
+    // 4. 并行为每一项生成二维码 PNG 和 TOTP 链接，结果按原顺序收集
+    // 终端下显示进度条；日志被重定向时退回逐行日志，避免破坏脚本
+    let interactive = io::stderr().is_terminal() && !cli.quiet;
+    let progress = if interactive {
+        ProgressBar::new(export.entries.len() as u64).with_style(
+            ProgressStyle::with_template("{spinner} [{elapsed_precise}] [{bar:40}] {pos}/{len} 剩余 {eta}")
+                .expect("进度条模板无效")
+                .progress_chars("=> "),
+        )
+    } else {
+        ProgressBar::hidden()
+    };
+    // --jobs 未指定时 rayon 按 CPU 核心数创建线程
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(cli.jobs.map_or(0, |jobs| jobs as usize))
+        .build()
+        .context("无法创建线程池")?;
+    let results: Vec<Result<Generated>> = pool.install(|| {
+        export.entries
+            .par_iter()
+            .zip(&filenames)
+            .map(|(entry, filename)| {
+                let result = generate_entry(entry, filename, cli, &render_options, output_dir);
+                completed.fetch_add(1, Ordering::Relaxed);
+                progress.inc(1);
+                result
+            })
+            .collect()
+    });
+    progress.finish_and_clear();
+
+    // TOTP 已全部构建完毕，立即清零条目中的明文 secret
+    for entry in &mut export.entries {
+        entry.secret.zeroize();
+    }
+
+    // 5. 按顺序输出日志，避免并行时各条目的输出交错
+    let mut uris = Vec::new();
+    let mut succeeded = 0;
+    // 失败的条目不会中断整体流程，最后统一汇报
+    let mut failures: Vec<(usize, anyhow::Error)> = Vec::new();
+    let mut tiles = Vec::new();
+    let mut frames = Vec::new();
+    let mut gallery_items = Vec::new();
+    let mut pdf_pages = Vec::new();
+    let mut written = Vec::new();
+    let mut reports = Vec::new();
+    let mut rejects = Vec::new();
+    let mut skipped_existing = 0;
+    let mut added = 0;
+    let mut saved_bytes = 0;
+    let mut borderline = Vec::new();
+    // 每种格式成功输出的数量，按 --format 的顺序汇总
+    let mut format_counts: Vec<(OutputFormat, usize)> = cli.format.iter().map(|&format| (format, 0)).collect();
+    let mut count = |format: OutputFormat| {
+        if let Some((_, n)) = format_counts.iter_mut().find(|(f, _)| *f == format) {
+            *n += 1;
+        }
+    };
+    let verb = if cli.dry_run { "将生成" } else { "已生成" };
+    let unicode = render::terminal_supports_unicode();
+    for (index, (entry, result)) in export.entries.iter().zip(results).enumerate() {
+        // 汇总输出所需的渲染同样可能失败，失败时整个条目按失败处理，不中断其余条目
+        let result = result.and_then(|generated| {
+            let extras = render_extras(entry, &generated.totp_url, cli, &render_options, unicode)?;
+            Ok((generated, extras))
+        });
+        let (generated, extras) = match result {
+            Ok(generated) => generated,
+            Err(e) => {
+                // --json-errors 时失败条目只在最终的 JSON 错误中列出
+                if !interactive && !cli.json_errors {
+                    info!("🔄 处理第 {}/{} 项: {} ({})",
+                             index + 1, export.entries.len(),
+                             entry.label_name, entry.username);
+                    error!("❌ {:#}", e);
+                    info!("");
+                }
+                reports.push(entry_report(index, entry, Err(&e)));
+                // 未指定 --rejects 时没有快照
+                if let Some(fields) = snapshots.get_mut(index) {
+                    rejects.push(report::Reject { entry: mem::take(fields), error: format!("{:#}", e) });
+                }
+                failures.push((index, e));
+                continue;
+            }
+        };
+        succeeded += 1;
+        reports.push(entry_report(index, entry, Ok(&generated)));
+        if let Err(e) = check_secret_len(generated.secret_len, cli.min_secret_bytes) {
+            warn!("⚠️  {} ({}): {}", entry.label_name, entry.username, e);
+        }
+        if let Err(e) = check_label(entry) {
+            warn!("⚠️  {} ({}): {}", entry.label_name, entry.username, e);
+        }
+        if generated.borderline {
+            borderline.push(format!("{} ({})", entry.label_name, entry.username));
+        }
+        if cli.fingerprint {
+            info!("🔏 {} ({}) 的 secret 指纹: {}", entry.label_name, entry.username, generated.fingerprint);
+        }
+
+        if cli.print_uri {
+            println!("{}", redact_url(&generated.totp_url));
+        }
+        if cli.has_format(OutputFormat::Uri) {
+            uris.push(generated.complete_url.clone());
+            count(OutputFormat::Uri);
+        }
+        written.extend(generated.link_path.clone());
+        for image in &generated.images {
+            written.push(image.path.clone());
+            saved_bytes += image.saved;
+            if image.skipped {
+                skipped_existing += 1;
+            } else {
+                added += 1;
+                count(image.format);
+            }
+        }
+        // 画廊只内嵌每个条目的第一种图片
+        if cli.html
+            && let Some(image) = generated.images.first()
+        {
+            gallery_items.push(gallery::Item {
+                image_path: image.path.clone(),
+                label: entry.label_name.clone(),
+                username: entry.username.clone(),
+            });
+        }
+        if cli.pdf.is_some() {
+            pdf_pages.push(pdf::Page {
+                issuer: entry.issuer().to_string(),
+                label: entry.label_name.clone(),
+                username: entry.username.clone(),
+                url: generated.totp_url.clone(),
+            });
+        }
+        if let Some((art, columns)) = &extras.terminal {
+            print_terminal_qr(entry, art, *columns);
+            count(OutputFormat::Terminal);
+        }
+        if let Some(frame) = extras.frame {
+            frames.push(frame);
+            count(OutputFormat::Apng);
+        }
+        if let Some(tile) = extras.tile {
+            tiles.push(tile);
+        }
+
+        if interactive {
+            continue;
+        }
+        info!("🔄 处理第 {}/{} 项: {} ({})", 
+                 index + 1, export.entries.len(), 
+                 entry.label_name, entry.username);
+        info!("🔑 Secret 长度: {} 字节 ({} 位)", generated.secret_len, generated.secret_len * 8);
+        for image in &generated.images {
+            if image.skipped {
+                info!("⏭️  已存在，跳过: {:?}", image.path);
+                continue;
+            }
+            info!("✅ {}二维码: {:?}", verb, image.path);
+            if cli.verify && !cli.dry_run && image.format.is_raster() {
+                info!("🔍 二维码解码校验通过");
+            }
+        }
+        if let Some(link_path) = &generated.link_path {
+            info!("🔗 {}链接: {:?}", verb, link_path);
+        }
+        if cli.scan_test && !generated.borderline {
+            info!("🔬 小尺寸扫描测试通过");
+        }
+        if cli.format != [OutputFormat::Uri] {
+            info!("🔗 TOTP URL: {}", redact_url(&generated.totp_url));
+        }
+        info!("");
+    }
+    if !borderline.is_empty() {
+        warn!("⚠️  以下 {} 个条目缩小到每模块 1 像素后无法识别，尺寸余量不足，打印时请勿过度缩小:", borderline.len());
+        for name in &borderline {
+            warn!("   - {}", name);
+        }
+    }
+
+    let summary = Summary {
+        total: loaded,
+        succeeded,
+        failed: failures.len(),
+        skipped: export.skipped,
+        not_selected: loaded - export.skipped - export.entries.len(),
+        existing: skipped_existing,
+        added: cli.append.then_some(added),
+        saved: cli.optimize.then_some(saved_bytes),
+        output_dir: (!terminal_only || cli.montage).then_some(output_dir.as_path()),
+    };
+    if format_counts.len() > 1 {
+        let counts: Vec<String> = format_counts
+            .iter()
+            .map(|(format, n)| format!("{} {} 个", format.name(), n))
+            .collect();
+        info!("📊 各格式输出: {}", counts.join("，"));
+    }
+
+    if let Some(report_path) = &cli.report {
+        report::write_report(report_path, &reports)?;
+        info!("📝 已写入报告: {}", report_path.display());
+    }
+    save_rejects(cli, &rejects)?;
+
+    // 试运行只汇报结果，不写入任何文件
+    if cli.dry_run {
+        info!("🧪 试运行完成：将生成 {} 项", succeeded);
+        summary.print(cli);
+        report_failures(cli, &failures, &export.entries, "未通过校验")?;
+        return Ok(());
+    }
+
+    if cli.montage {
+        let sheet_path = output_dir.join("contact-sheet.png");
+        montage::contact_sheet(&tiles, cli.columns)
+            .save(&sheet_path)
+            .with_context(|| format!("写入拼图失败: {:?}", sheet_path))?;
+        info!("🖼️  已生成拼图: {:?}", sheet_path);
+        written.push(sheet_path);
+    }
+
+    if cli.has_format(OutputFormat::Apng) && !frames.is_empty() {
+        let apng_path = output_dir.join(apng::FILE_NAME);
+        apng::write_apng(&apng_path, &frames, cli.frame_delay, render_options.bg)?;
+        info!("🎞️  已生成 {} 帧的动画二维码: {:?}", frames.len(), apng_path);
+        written.push(apng_path);
+    }
+
+    if let Some(pdf_path) = &cli.pdf {
+        pdf::write_pdf(pdf_path, &pdf_pages, &render_options)?;
+        info!("🖨️  已生成 PDF（{} 页）: {}", pdf_pages.len(), pdf_path.display());
+    }
+
+    if cli.html {
+        let index_path = output_dir.join("index.html");
+        gallery::write_gallery(&index_path, &gallery_items)?;
+        info!("🌐 已生成画廊页面: {:?}", index_path);
+        written.push(index_path);
+    }
+
+    if cli.has_format(OutputFormat::Uri) {
+        let uris_path = output_dir.join("uris.txt");
+        let mut content = Zeroizing::new(uris.iter().map(|uri| uri.as_str()).collect::<Vec<_>>().join("\n"));
+        content.push('\n');
+        fs::write(&uris_path, content.as_bytes())
+            .with_context(|| format!("写入链接文件失败: {:?}", uris_path))?;
+        info!("🎉 已导出 {} 条 otpauth 链接: {:?}", uris.len(), uris_path);
+        written.push(uris_path);
+    }
+    if cli.checksums && !written.is_empty() {
+        let sums_path = report::write_checksums(output_dir, &written)?;
+        info!("🧾 已写入 {} 个文件的校验和: {:?}", written.len(), sums_path);
+    }
+    // 链接文件与 uris.txt 同样包含明文 secret，一并打包，--remove-loose 时一并删除
+    if let (Some(zip_path), Some(password)) = (&cli.zip, &cli.password) {
+        archive::write_encrypted_zip(zip_path, output_dir, &written, password)?;
+        info!("🔒 已打包 {} 个文件到加密压缩包: {}", written.len(), zip_path.display());
+        if cli.remove_loose {
+            for path in &written {
+                fs::remove_file(path)
+                    .with_context(|| format!("删除文件失败: {}", path.display()))?;
+            }
+            info!("🧹 已删除 {} 个未加密的文件", written.len());
+        }
+    }
+    if cli.has_format(OutputFormat::Terminal) {
+        info!("🎉 已在终端输出 {} 个二维码", succeeded);
+    }
+    if cli.has_images() {
+        if failures.is_empty() {
+            info!("🎉 所有二维码和 TOTP 链接生成完成！");
+        } else {
+            info!("🎉 其余 {} 项二维码和 TOTP 链接已生成", succeeded);
+        }
+        info!("📁 二维码保存在: {}/ 目录", output_dir.display());
+        info!("🔗 TOTP 链接保存在: {}/ 目录", output_dir.join(LINKS_DIR).display());
+    }
+
+    summary.print(cli);
+    report_failures(cli, &failures, &export.entries, "生成失败")
+}
+
+/// 按 --force-algorithm / --force-digits / --force-period 统一覆盖条目参数，并警告被覆盖的字段
+fn force_parameters(cli: &Cli, entries: &mut [TotpEntry]) {
+    let mut forced = Vec::new();
+    let mut changed = 0;
+    if let Some(algorithm) = &cli.force_algorithm {
+        forced.push(format!("algorithm={}", algorithm));
+        for entry in entries.iter_mut() {
+            if normalize_algorithm(&entry.algorithm) != *algorithm {
+                changed += 1;
+            }
+            entry.algorithm = algorithm.clone();
+        }
+    }
+    if let Some(digits) = cli.force_digits {
+        forced.push(format!("digits={}", digits));
+        changed += entries.iter().filter(|entry| entry.digits != digits).count();
+        entries.iter_mut().for_each(|entry| entry.digits = digits);
+    }
+    if let Some(period) = cli.force_period {
+        forced.push(format!("period_time={}", period));
+        changed += entries.iter().filter(|entry| entry.period_time != period).count();
+        entries.iter_mut().for_each(|entry| entry.period_time = period);
+    }
+    if !forced.is_empty() {
+        warn!("⚠️  已强制覆盖 {}，共替换 {} 处与输入不同的值", forced.join("、"), changed);
+    }
+}
+
+/// --strict 下的逐条校验：secret 过短或名称不合法时该条目记为失败，其余条目照常生成
+///
+/// secret 无法解码的条目不在这里报错，交给构建链接时汇报
+fn check_strict(cli: &Cli, entry: &TotpEntry) -> Result<()> {
+    if !cli.source.strict {
+        return Ok(());
+    }
+    if let Ok(secret) = decode_secret(entry) {
+        check_secret_len(secret.len(), cli.min_secret_bytes)
+            .with_context(|| format!("secret 过短: {} ({})", entry.label_name, entry.username))?;
+    }
+    check_label(entry)
+        .with_context(|| format!("名称不合法: {} ({})", entry.label_name, entry.username))?;
+    Ok(())
+}
+
+/// 启动 --time-limit 看门狗：从 started 起超过 limit 秒仍未结束时输出错误并以 TIMEOUT 退出码结束进程；
+/// 返回的 Sender 被释放（本次运行结束）时看门狗随之退出
+fn start_watchdog(cli: &Cli, started: Instant, limit: u64, completed: Arc<AtomicUsize>, total: usize) -> mpsc::Sender<()> {
+    let (sender, receiver) = mpsc::channel::<()>();
+    let remaining = Duration::from_secs(limit).saturating_sub(started.elapsed());
+    let json_errors = cli.json_errors;
+    thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(remaining) {
+            let completed = completed.load(Ordering::Relaxed);
+            let error = anyhow::Error::new(exit::Timeout { limit, completed, total });
+            exit::print(&error, json_errors);
+            process::exit(exit::TIMEOUT.into());
+        }
+    });
+    sender
+}
+
+/// 一次运行的结果统计
+struct Summary<'a> {
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    /// 导入时跳过的不支持的条目数（如非 TOTP）
+    skipped: usize,
+    /// 被去重、--filter、--limit、--tui 或 --only 排除的条目数
+    not_selected: usize,
+    /// --no-clobber / --append 保留的已有二维码文件数
+    existing: usize,
+    /// --append 新增的二维码文件数，未指定时为 None
+    added: Option<usize>,
+    /// --optimize 节省的 PNG 字节数，未指定时为 None
+    saved: Option<usize>,
+    /// 只输出到终端时为 None
+    output_dir: Option<&'a Path>,
+}
+
+impl Summary<'_> {
+    /// 输出运行汇总；--quiet 时仍向标准错误写一行 key=value，--json-errors 时改为一行 JSON，便于脚本解析
+    fn print(&self, cli: &Cli) {
+        let output_dir = self.output_dir.map(|dir| dir.display().to_string());
+        if !cli.quiet {
+            info!("📊 运行汇总");
+            info!("   总条目: {}", self.total);
+            info!("   成功: {}", self.succeeded);
+            info!("   失败: {}", self.failed);
+            info!("   跳过: {}（不支持的条目）", self.skipped);
+            info!("   未选中: {}（去重 / 筛选 / 数量限制 / 勾选）", self.not_selected);
+            if self.existing > 0 {
+                info!("   已存在的文件: {}", self.existing);
+            }
+            if let Some(added) = self.added {
+                info!("   新增文件: {}", added);
+            }
+            if let Some(saved) = self.saved {
+                info!("   PNG 优化节省: {} 字节", saved);
+            }
+            if let Some(output_dir) = &output_dir {
+                info!("   输出目录: {}", output_dir);
+            }
+        }
+        if cli.json_errors {
+            let json = serde_json::json!({
+                "kind": "summary",
+                "total": self.total,
+                "succeeded": self.succeeded,
+                "failed": self.failed,
+                "skipped": self.skipped,
+                "not_selected": self.not_selected,
+                "existing": self.existing,
+                "added": self.added,
+                "saved_bytes": self.saved,
+                "output_dir": output_dir,
+            });
+            eprintln!("{}", json);
+        } else if cli.quiet {
+            let added = self.added.map(|added| format!(" added={}", added)).unwrap_or_default();
+            let saved = self.saved.map(|saved| format!(" saved_bytes={}", saved)).unwrap_or_default();
+            eprintln!(
+                "summary total={} succeeded={} failed={} skipped={} not_selected={} existing={}{}{} output_dir={}",
+                self.total, self.succeeded, self.failed, self.skipped, self.not_selected, self.existing, added, saved,
+                output_dir.as_deref().unwrap_or("-")
+            );
+        }
+    }
+}
+
+/// 汇总输出所有失败的条目，存在失败时返回错误以便以非零状态退出；--json-errors 时只由返回的错误输出
+fn report_failures(cli: &Cli, failures: &[(usize, anyhow::Error)], entries: &[TotpEntry], what: &str) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    if !cli.json_errors {
+        error!("❌ 以下 {} 项{}:", failures.len(), what);
+        for (index, e) in failures {
+            error!("   - 第 {} 项: {:#}", index + 1, e);
+        }
+    }
+    Err(PartialFailure {
+        message: format!("共有 {} 项{}", failures.len(), what),
+        entries: failures
+            .iter()
+            .map(|(index, e)| FailedEntry {
+                entry_index: index + 1,
+                label: entries[*index].label_name.clone(),
+                username: entries[*index].username.clone(),
+                message: format!("{:#}", e),
+            })
+            .collect(),
+    }
+    .into())
+}
+
+/// 去掉 secret（按解码后的字节比较）、算法、位数、周期都相同的重复条目，只保留第一个
+///
+/// secret 无法解码的条目原样保留，交给后续流程报错；返回跳过的条目数
+fn dedup_entries(entries: &mut Vec<TotpEntry>) -> usize {
+    let mut seen = HashMap::new();
+    let before = entries.len();
+    let mut index = 0;
+    entries.retain(|entry| {
+        index += 1;
+        let Ok(bytes) = decode_secret(entry) else {
+            return true;
+        };
+        // 以摘要作为键，避免在表中保存明文 secret
+        let key = (report::sha256_hex(&bytes), normalize_algorithm(&entry.algorithm), entry.digits, entry.period_time);
+        if let Some(first) = seen.get(&key) {
+            info!("⏭️  跳过重复条目: 第 {} 项 {} ({})，与第 {} 项相同",
+                     index, entry.label_name, entry.username, first);
+            return false;
+        }
+        seen.insert(key, index);
+        true
+    });
+    before - entries.len()
+}
+
+/// 按指定字段不区分大小写地稳定排序
+fn sort_entries(entries: &mut [TotpEntry], key: SortKey) {
+    entries.sort_by_cached_key(|entry| {
+        let label = entry.label_name.to_lowercase();
+        let username = entry.username.to_lowercase();
+        match key {
+            SortKey::Label => (label, username),
+            SortKey::Username => (username, label),
+        }
+    });
+}
+
+/// --reproducible 的排序：依次比较服务名称、账户名（先不区分大小写）、issuer 和参数，均相同时按解码后 secret 的指纹
+fn sort_reproducible(entries: &mut [TotpEntry]) {
+    entries.sort_by_cached_key(|entry| {
+        (
+            entry.label_name.to_lowercase(),
+            entry.username.to_lowercase(),
+            entry.label_name.clone(),
+            entry.username.clone(),
+            entry.issuer().to_string(),
+            normalize_algorithm(&entry.algorithm),
+            entry.digits,
+            entry.period_time,
+            // 按解码后的字节比较，大小写、空格或填充不同的等价写法排序一致
+            match decode_secret(entry) {
+                Ok(secret) => secret_fingerprint(&secret),
+                Err(_) => secret_fingerprint(entry.secret.as_bytes()),
+            },
+        )
+    });
+}
+
+/// 单个条目的生成结果
+struct Generated {
+    secret_len: usize,
+    fingerprint: String,
+    totp_url: Zeroizing<String>,       // 链接中包含 secret，释放时清零
+    complete_url: Zeroizing<String>,   // 显式包含全部参数，写入 uris.txt
+    images: Vec<GeneratedImage>,       // 按 --format 顺序
+    link_path: Option<PathBuf>,
+    borderline: bool,                  // --scan-test 下缩小后无法识别
+}
+
+/// 顺序输出阶段为单个条目额外渲染的内容
+struct Extras {
+    terminal: Option<(String, usize)>,   // --format terminal 的字符画及其列数
+    frame: Option<DynamicImage>,         // --format apng 的一帧
+    tile: Option<montage::Tile>,         // --montage 的格子
+}
+
+/// 渲染需要在顺序输出阶段汇总的内容，任何一项失败时该条目记为失败
+fn render_extras(entry: &TotpEntry, url: &str, cli: &Cli, render_options: &RenderOptions, unicode: bool) -> Result<Extras> {
+    let terminal = if cli.has_format(OutputFormat::Terminal) {
+        Some(render::render_terminal(url, render_options, unicode)
+            .with_context(|| format!("生成二维码失败: {} ({})", entry.label_name, entry.username))?)
+    } else {
+        None
+    };
+    let frame = if cli.has_format(OutputFormat::Apng) && !cli.dry_run {
+        let frame = render::render_image(url, render_options)
+            .with_context(|| format!("生成动画帧失败: {} ({})", entry.label_name, entry.username))?;
+        Some(if cli.caption {
+            let scale = (render_options.scale / 4).max(1);
+            caption::add_caption(frame, &[&entry.label_name, &entry.username], scale, render_options.fg, render_options.bg)
+        } else {
+            frame
+        })
+    } else {
+        None
+    };
+    let tile = if cli.montage && !cli.dry_run {
+        let image = render::render_canvas(url, render_options)
+            .with_context(|| format!("生成拼图二维码失败: {} ({})", entry.label_name, entry.username))?;
+        Some(montage::Tile {
+            image,
+            label: entry.label_name.clone(),
+            username: entry.username.clone(),
+        })
+    } else {
+        None
+    };
+    Ok(Extras { terminal, frame, tile })
+}
+
+/// 单个条目的一种图片输出
+struct GeneratedImage {
+    format: OutputFormat,
+    path: PathBuf,
+    sha256: String,
+    skipped: bool,   // --no-clobber / --append 下文件已存在，未重新生成
+    saved: usize,    // --optimize 节省的字节数
+}
+
+/// 指定了 --rejects 时写入失败条目
+fn save_rejects(cli: &Cli, rejects: &[report::Reject]) -> Result<()> {
+    if let Some(rejects_path) = &cli.rejects {
+        report::write_rejects(rejects_path, rejects)?;
+        info!("🗂️  已将 {} 个失败条目写入: {}", rejects.len(), rejects_path.display());
+    }
+    Ok(())
+}
+
+/// 将条目及其处理结果转换为报告记录
+fn entry_report(index: usize, entry: &TotpEntry, result: Result<&Generated, &anyhow::Error>) -> EntryReport {
+    let mut report = EntryReport {
+        index: index + 1,
+        label: entry.label_name.clone(),
+        username: entry.username.clone(),
+        algorithm: entry.algorithm.clone(),
+        digits: entry.digits,
+        period: entry.period_time,
+        outcome: Outcome::Success,
+        image_path: None,
+        link_path: None,
+        sha256: None,
+        other_images: Vec::new(),
+        error: None,
+    };
+    match result {
+        Ok(generated) => {
+            let mut images = generated.images.iter();
+            if let Some(first) = images.next() {
+                report.image_path = Some(first.path.clone());
+                report.sha256 = Some(first.sha256.clone());
+            }
+            report.other_images = images
+                .map(|image| ImageReport { path: image.path.clone(), sha256: image.sha256.clone() })
+                .collect();
+            report.link_path = generated.link_path.clone();
+        }
+        Err(e) => {
+            report.outcome = Outcome::Failed;
+            report.error = Some(format!("{:#}", e));
+        }
+    }
+    report
+}
+
+/// 将全部条目打包为 otpauth-migration 二维码，超出单个二维码容量时拆分为 migration-1.png、migration-2.png ...
+fn write_migration(
+    cli: &Cli,
+    entries: &[TotpEntry],
+    mut snapshots: Vec<Snapshot>,
+    render_options: &RenderOptions,
+    output_dir: &Path,
+) -> Result<()> {
+    let migration::Batches { uris, skipped } = migration::encode_batches(
+        entries,
+        |uri| render::encode(uri, render_options.ecc).is_ok(),
+        |entry| check_strict(cli, entry),
+    )?;
+    if cli.rejects.is_some() {
+        let rejects: Vec<report::Reject> = skipped
+            .iter()
+            .map(|(index, e)| report::Reject { entry: mem::take(&mut snapshots[*index]), error: format!("{:#}", e) })
+            .collect();
+        save_rejects(cli, &rejects)?;
+    }
+    if uris.is_empty() {
+        report_failures(cli, &skipped, entries, "无法导出为迁移格式")?;
+        return Err(anyhow::anyhow!("没有可以导出为迁移格式的条目"));
+    }
+
+    if !cli.dry_run {
+        create_output_dir(cli, output_dir)?;
+    }
+    let verb = if cli.dry_run { "将生成" } else { "已生成" };
+    let mut written = Vec::new();
+    for (index, uri) in uris.iter().enumerate() {
+        let path = output_dir.join(format!("migration-{}.png", index + 1));
+        if !cli.dry_run {
+            let png = render::render_png(uri, render_options)
+                .with_context(|| format!("生成迁移二维码失败: {:?}", path))?;
+            fs::write(&path, png)
+                .with_context(|| format!("写入二维码文件失败: {:?}", path))?;
+            if cli.verify {
+                let written = fs::read(&path)
+                    .with_context(|| format!("读取二维码文件失败: {:?}", path))?;
+                verify::verify_image(&written, uri)
+                    .with_context(|| format!("二维码校验失败: {:?}", path))?;
+            }
+        }
+        info!("✅ {}迁移二维码: {:?}", verb, path);
+        written.push(path);
+    }
+    if cli.checksums {
+        let sums_path = report::write_checksums(output_dir, &written)?;
+        info!("🧾 已写入 {} 个文件的校验和: {:?}", written.len(), sums_path);
+    }
+    info!("🎉 已将 {} 个条目打包为 {} 张迁移二维码，可在 Google Authenticator 中依次扫码导入",
+          entries.len() - skipped.len(), uris.len());
+    report_failures(cli, &skipped, entries, "无法导出为迁移格式")
+}
+
+/// 为单个条目生成二维码与链接文件（可在多个线程中并行调用）
+fn generate_entry(
+    entry: &TotpEntry,
+    base_filename: &str,
+    cli: &Cli,
+    render_options: &RenderOptions,
+    output_dir: &Path,
+) -> Result<Generated> {
+    // 校验放在最前，未通过的条目不写入任何文件
+    check_strict(cli, entry)?;
+    let (secret_len, fingerprint, totp_url, complete_url) = match entry.kind {
+        OtpKind::Totp => {
+            let totp = build_totp(entry)
+                .with_context(|| format!("构建 TOTP 失败: {} ({})", entry.label_name, entry.username))?;
+            (totp.secret.len(), secret_fingerprint(&totp.secret), Zeroizing::new(totp.get_url()), Zeroizing::new(full_url(&totp)))
+        }
+        // HOTP 链接本身已包含全部参数
+        OtpKind::Hotp => {
+            let url = Zeroizing::new(build_hotp_url(entry)
+                .with_context(|| format!("构建 HOTP 失败: {} ({})", entry.label_name, entry.username))?);
+            let secret = decode_secret(entry)?;
+            (secret.len(), secret_fingerprint(&secret), url.clone(), url)
+        }
+    };
+    // 链接只计算一次，各图片格式共用；uri / terminal 不生成图片文件
+    let mut images = Vec::new();
+    for format in cli.format.iter().copied().filter(|format| format.has_image()) {
+        let image_path = output_dir.join(format!("{}.{}", base_filename, format.extension()));
+        if cli.keep_existing() && image_path.exists() {
+            let existing = fs::read(&image_path)
+                .with_context(|| format!("读取二维码文件失败: {:?}", image_path))?;
+            let sha256 = report::sha256_hex(&existing);
+            images.push(GeneratedImage { format, path: image_path, sha256, skipped: true, saved: 0 });
+            continue;
+        }
+
+        let (image, saved) = match format {
+            OutputFormat::Svg => render::render_svg(&totp_url, render_options).map(|svg| (svg.into_bytes(), 0)),
+            raster => encode_raster(raster, entry, &totp_url, cli, render_options),
+        }
+        .with_context(|| format!("生成二维码失败: {} ({})", entry.label_name, entry.username))?;
+
+        // 保存二维码图片
+        let sha256 = report::sha256_hex(&image);
+        if !cli.dry_run {
+            create_parent_dir(&image_path)?;
+            fs::write(&image_path, image)
+                .with_context(|| format!("写入二维码文件失败: {:?}", image_path))?;
+
+            // 从磁盘读回并解码，防止写入的备份已损坏
+            if cli.verify && format.is_raster() {
+                let written = fs::read(&image_path)
+                    .with_context(|| format!("读取二维码文件失败: {:?}", image_path))?;
+                verify::verify_image(&written, &totp_url)
+                    .with_context(|| format!("二维码校验失败: {:?}", image_path))?;
+            }
+        }
+        images.push(GeneratedImage { format, path: image_path, sha256, skipped: false, saved });
+    }
+
+    // 保存 TOTP 链接
+    let link_path = if images.is_empty() {
+        None
+    } else {
+        let link_path = output_dir.join(LINKS_DIR).join(format!("{}.txt", base_filename));
+        let keep_existing = cli.keep_existing() && link_path.exists();
+        if !cli.dry_run && !keep_existing {
+            create_parent_dir(&link_path)?;
+            fs::write(&link_path, totp_url.as_bytes())
+                .with_context(|| format!("写入链接文件失败: {:?}", link_path))?;
+        }
+        Some(link_path)
+    };
+
+    // 小尺寸扫描测试放在最后，未通过时正常尺寸的图片仍已写入
+    let borderline = if cli.scan_test {
+        !verify::scan_test(&totp_url, render_options)
+            .with_context(|| format!("小尺寸扫描测试失败: {} ({})", entry.label_name, entry.username))?
+    } else {
+        false
+    };
+
+    Ok(Generated {
+        secret_len,
+        fingerprint,
+        totp_url,
+        complete_url,
+        images,
+        link_path,
+        borderline,
+    })
+}
+
+/// 渲染位图二维码（按需添加说明文字）并编码为指定格式，返回文件内容和 --optimize 节省的字节数
+fn encode_raster(
+    format: OutputFormat,
+    entry: &TotpEntry,
+    url: &str,
+    cli: &Cli,
+    render_options: &RenderOptions,
+) -> Result<(Vec<u8>, usize)> {
+    let image = render::render_image(url, render_options)?;
+    let image = if cli.caption {
+        let scale = (render_options.scale / 4).max(1);
+        caption::add_caption(image, &[&entry.label_name, &entry.username], scale, render_options.fg, render_options.bg)
+    } else {
+        image
+    };
+    match format {
+        // 优化后的 PNG 总是解码核对一次，确保内容与原始链接一致
+        OutputFormat::Png if cli.optimize => {
+            let plain = render::encode_png(&image)?;
+            let optimized = render::encode_png_optimized(&image)?;
+            verify::verify_image(&optimized, url).context("优化后的 PNG 校验失败")?;
+            let saved = plain.len().saturating_sub(optimized.len());
+            Ok((optimized, saved))
+        }
+        OutputFormat::Png => Ok((render::encode_png(&image)?, 0)),
+        OutputFormat::Webp => Ok((render::encode_webp(&image)?, 0)),
+        OutputFormat::Jpeg => Ok((render::encode_jpeg(&image, cli.quality)?, 0)),
+        _ => unreachable!("非图片格式已被过滤"),
+    }
+}
+
+/// --stdout：把唯一选中条目的 PNG 写到标准输出，日志仍写到标准错误
+fn write_stdout(cli: &Cli, entries: &[TotpEntry], render_options: &RenderOptions) -> Result<()> {
+    let (index, entry) = match (cli.only, entries) {
+        (Some(only), _) => (only as usize - 1, &entries[only as usize - 1]),
+        (None, [entry]) => (0, entry),
+        (None, _) => {
+            return Err(anyhow::anyhow!(
+                "--stdout 只能输出一个条目，当前选中了 {} 个，请用 --only / --filter / --limit 缩小范围",
+                entries.len()
+            ));
+        }
+    };
+    if let Err(e) = check_strict(cli, entry) {
+        return report_failures(cli, &[(index, e)], entries, "未通过 --strict 校验");
+    }
+    if io::stdout().is_terminal() {
+        return Err(anyhow::anyhow!("拒绝向终端输出 PNG 二进制数据，请重定向到文件或通过管道传给其他程序"));
+    }
+    let url = Zeroizing::new(match entry.kind {
+        OtpKind::Totp => build_totp(entry)
+            .with_context(|| format!("构建 TOTP 失败: {} ({})", entry.label_name, entry.username))?
+            .get_url(),
+        OtpKind::Hotp => build_hotp_url(entry)
+            .with_context(|| format!("构建 HOTP 失败: {} ({})", entry.label_name, entry.username))?,
+    });
+    let (image, _) = encode_raster(OutputFormat::Png, entry, &url, cli, render_options)
+        .with_context(|| format!("生成二维码失败: {} ({})", entry.label_name, entry.username))?;
+    if cli.verify {
+        verify::verify_image(&image, &url).context("二维码校验失败")?;
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&image).and_then(|()| stdout.flush()).context("写入标准输出失败")?;
+    info!("✅ 已将 {} ({}) 的 PNG 二维码写到标准输出（{} 字节）", entry.label_name, entry.username, image.len());
+    Ok(())
+}
+
+/// 在标准输出打印条目标题与字符画二维码，超出终端宽度时提示
+fn print_terminal_qr(entry: &TotpEntry, art: &str, columns: usize) {
+    if let Some((Width(width), _)) = terminal_size::terminal_size()
+        && columns > width as usize
+    {
+        warn!("⚠️  二维码宽 {} 列，超过终端宽度 {} 列，可减小 --margin 或放大终端窗口", columns, width);
+    }
+    println!("{} ({})", entry.label_name, entry.username);
+    print!("{}", art);
+    println!();
+}
+
+/// 生成包含全部参数的 otpauth 链接
+///
+/// `get_url` 会省略默认的 algorithm/digits/period，这里显式补全，便于迁移到其他应用
+fn full_url(totp: &TOTP) -> String {
+    let url = totp.get_url();
+    let (base, query) = url.split_once('?').unwrap_or((&url, ""));
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|p| p.starts_with("secret=") || p.starts_with("issuer="))
+        .collect();
+    let algorithm = format!("algorithm={}", totp.algorithm);
+    let digits = format!("digits={}", totp.digits);
+    let period = format!("period={}", totp.step);
+    params.extend([algorithm.as_str(), digits.as_str(), period.as_str()]);
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// 输出目录下存放 TOTP 链接文件的子目录
+const LINKS_DIR: &str = "links";
+
+/// --latest-symlink 创建的链接名
+const LATEST_LINK: &str = "latest";
+
+/// --group-by 字段为空的条目所在的子目录
+const UNGROUPED_DIR: &str = "_ungrouped";
+
+/// 文件名模板支持的占位符
+const TEMPLATE_PLACEHOLDERS: [&str; 4] = ["{label}", "{username}", "{index}", "{issuer}"];
+
+/// 检查文件名模板中没有无法识别的占位符
+fn validate_template(template: &str) -> Result<()> {
+    let mut rest = template.to_string();
+    for placeholder in TEMPLATE_PLACEHOLDERS {
+        rest = rest.replace(placeholder, "");
+    }
+    if rest.contains('{') || rest.contains('}') {
+        return Err(anyhow::anyhow!(
+            "文件名模板包含无法识别的占位符: {}，仅支持 {}",
+            template,
+            TEMPLATE_PLACEHOLDERS.join(" ")
+        ));
+    }
+    Ok(())
+}
+
+/// 按模板展开文件名（不含扩展名），每一级路径都会清洗，展开为空时退回序号
+fn expand_template(template: &str, entry: &TotpEntry, index: usize) -> String {
+    let ordinal = (index + 1).to_string();
+    // 先逐个清洗占位符的值，避免某个字段整体被过滤后只剩下分隔符
+    let value = |raw: &str| if raw.is_empty() { String::new() } else { sanitize(raw) };
+    let expanded = template
+        .replace("{label}", &value(&entry.label_name))
+        .replace("{username}", &value(&entry.username))
+        .replace("{index}", &ordinal)
+        .replace("{issuer}", &value(entry.issuer()));
+
+    let mut segments: Vec<String> = expanded
+        .split('/')
+        .filter(|segment| !segment.trim().is_empty())
+        .map(sanitize)
+        .collect();
+    if segments.is_empty() {
+        segments.push(ordinal);
+    }
+    segments.join("/")
+}
+
+/// 分组子目录名，字段为空时归入 _ungrouped
+fn group_dir(raw: &str) -> String {
+    if raw.trim().is_empty() {
+        UNGROUPED_DIR.to_string()
+    } else {
+        sanitize(raw)
+    }
+}
+
+/// 在已使用的输出路径中查重，冲突时依次尝试 name-1、name-2 ...
+///
+/// 按完整相对路径（含分组子目录）逐级比较并忽略大小写，避免在大小写不敏感的文件系统上互相覆盖
+fn unique_filename(base: &str, seen: &mut HashSet<PathBuf>) -> String {
+    let key = |name: &str| PathBuf::from(name.to_lowercase());
+    let mut candidate = base.to_string();
+    let mut suffix = 1;
+    while !seen.insert(key(&candidate)) {
+        candidate = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    candidate
+}
+
+/// 输出目录；指定 --timestamped 时为其下以本次运行时间命名的子目录
+fn resolve_output_dir(cli: &Cli) -> PathBuf {
+    if cli.timestamped {
+        cli.output_dir.join(Local::now().format("%Y-%m-%dT%H-%M-%S").to_string())
+    } else {
+        cli.output_dir.clone()
+    }
+}
+
+/// 创建输出目录，按需更新 latest 链接
+fn create_output_dir(cli: &Cli, output_dir: &Path) -> Result<()> {
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("无法创建输出目录: {}", output_dir.display()))?;
+    if cli.timestamped {
+        info!("📁 本次输出目录: {}", output_dir.display());
+    }
+    if cli.latest_symlink {
+        link_latest(&cli.output_dir, output_dir)?;
+    }
+    Ok(())
+}
+
+/// 将 <输出目录>/latest 指向本次运行的子目录；已存在同名的普通文件或目录时不覆盖
+fn link_latest(base: &Path, output_dir: &Path) -> Result<()> {
+    let link = base.join(LATEST_LINK);
+    let target = output_dir.file_name().map(PathBuf::from).unwrap_or_else(|| output_dir.to_path_buf());
+    match fs::symlink_metadata(&link) {
+        Ok(metadata) if metadata.file_type().is_symlink() => remove_link(&link)
+            .with_context(|| format!("无法删除旧的链接: {}", link.display()))?,
+        Ok(_) => {
+            warn!("⚠️  {} 已存在且不是符号链接，未更新", link.display());
+            return Ok(());
+        }
+        Err(_) => {}
+    }
+    #[cfg(unix)]
+    let created = std::os::unix::fs::symlink(&target, &link);
+    #[cfg(windows)]
+    let created = std::os::windows::fs::symlink_dir(&target, &link);
+    #[cfg(not(any(unix, windows)))]
+    let created: io::Result<()> = Err(io::Error::from(io::ErrorKind::Unsupported));
+    match created {
+        Ok(()) => info!("🔗 {} -> {}", link.display(), target.display()),
+        Err(e) => warn!("⚠️  无法创建符号链接 {}: {}", link.display(), e),
+    }
+    Ok(())
+}
+
+/// 删除符号链接本身（Windows 上目录链接需要按目录删除）
+fn remove_link(link: &Path) -> io::Result<()> {
+    fs::remove_file(link).or_else(|e| if cfg!(windows) { fs::remove_dir(link) } else { Err(e) })
+}
+
+/// 将已存在且非空的输出目录重命名为 <目录>.bak-<时间戳>，目录不存在或为空时不做处理
+fn backup_output_dir(output_dir: &Path) -> Result<()> {
+    let Ok(mut children) = fs::read_dir(output_dir) else {
+        return Ok(());
+    };
+    if children.next().is_none() {
+        return Ok(());
+    }
+    let mut backup = output_dir.as_os_str().to_owned();
+    backup.push(format!(".bak-{}", Local::now().format("%Y%m%d-%H%M%S")));
+    let backup = PathBuf::from(backup);
+    if backup.exists() {
+        return Err(anyhow::anyhow!("备份目录已存在: {}", backup.display()));
+    }
+    fs::rename(output_dir, &backup)
+        .with_context(|| format!("无法备份输出目录 {} 到 {}", output_dir.display(), backup.display()))?;
+    info!("🗄️  已将原输出目录备份为: {}", backup.display());
+    Ok(())
+}
+
+/// 确保文件所在目录存在（文件名模板可能包含子目录）
+fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("无法创建目录: {}", parent.display()))?;
+    }
+    Ok(())
+}
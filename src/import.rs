@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use chrono::{SecondsFormat, Utc};
+use std::{fs, path::Path};
+use totp_rs::{Algorithm, TOTP};
+
+use gen_totp_pic::{OtpKind, TotpEntry, TotpExport};
+
+/// 读取 otpauth 链接列表，生成与输入格式一致的 JSON 导出文件
+pub fn import_uris(input: &Path, output: &Path) -> Result<()> {
+    info!("📂 读取文件: {}", input.display());
+
+    let data = fs::read_to_string(input)
+        .with_context(|| format!("无法读取文件: {}", input.display()))?;
+
+    let mut entries = Vec::new();
+    let mut failed = 0;
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_uri(line) {
+            Ok(entry) => {
+                info!("✅ 第 {} 行: {} ({})", index + 1, entry.label_name, entry.username);
+                entries.push(entry);
+            }
+            Err(e) => {
+                warn!("⚠️  第 {} 行不是有效的 otpauth 链接，已跳过: {:#}", index + 1, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let export = TotpExport {
+        export_time: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped: 0,
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .context("JSON 序列化失败")?;
+    fs::write(output, json)
+        .with_context(|| format!("写入 JSON 文件失败: {}", output.display()))?;
+
+    info!("🎉 已导入 {} 条，跳过 {} 行: {}", export.entries.len(), failed, output.display());
+    Ok(())
+}
+
+/// 将单条 otpauth 链接解析为 TOTP 条目
+fn parse_uri(uri: &str) -> Result<TotpEntry> {
+    // from_url 会处理 URL 编码的 label 以及 issuer 参数
+    let totp = TOTP::from_url_unchecked(uri)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    Ok(TotpEntry {
+        // 没有 issuer 时用账户名充当 label
+        label_name: totp.issuer.clone().unwrap_or_else(|| totp.account_name.clone()),
+        username: totp.account_name.clone(),
+        secret: totp.get_secret_base32(),
+        secret_format: None,
+        issuer: None,
+        kind: OtpKind::Totp,
+        counter: None,
+        skew: None,
+        // Steam 的 Display 输出为 SHA1，这里需要保留 Steam 标记
+        algorithm: match totp.algorithm {
+            Algorithm::Steam => "STEAM".to_string(),
+            other => other.to_string(),
+        },
+        digits: totp.digits as u32,
+        period_time: totp.step,
+    })
+}
@@ -0,0 +1,407 @@
+mod aegis;
+mod andotp;
+mod twofas;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use log::{info, warn};
+use clap::ValueEnum;
+use flate2::bufread::MultiGzDecoder;
+use std::{fs::File, io::{self, BufRead, BufReader, Read}, path::{Component, Path, PathBuf}, time::Duration};
+
+use gen_totp_pic::{TotpEntry, TotpExport, FILE_SECRET_PREFIX};
+use zeroize::Zeroizing;
+
+use crate::{cli::InputArgs, migration};
+
+/// 输入文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// 本工具的 JSON 导出格式
+    Json,
+    /// 表头为 username,label_name,secret,algorithm,digits,period_time 的 CSV
+    Csv,
+    /// 与 JSON 结构相同的 YAML
+    Yaml,
+    /// Aegis Authenticator 明文 JSON 导出
+    Aegis,
+    /// andOTP 明文 JSON 备份
+    Andotp,
+    /// 2FAS Auth 明文 JSON 备份（.2fas）
+    #[value(name = "2fas")]
+    TwoFas,
+    /// Google Authenticator 导出的 otpauth-migration:// 链接（每行一条）
+    Migration,
+}
+
+impl InputFormat {
+    /// 根据文件扩展名推断输入格式，没有扩展名时按 JSON 处理；`.gz` 后缀按去掉后的扩展名判断
+    pub fn detect(path: &Path) -> Result<Self> {
+        if is_gz(path) {
+            return Self::detect(Path::new(path.file_stem().unwrap_or_default()));
+        }
+        let Some(ext) = path.extension() else {
+            return Ok(InputFormat::Json);
+        };
+        match ext.to_string_lossy().to_ascii_lowercase().as_str() {
+            "json" => Ok(InputFormat::Json),
+            "csv" => Ok(InputFormat::Csv),
+            "yaml" | "yml" => Ok(InputFormat::Yaml),
+            "2fas" => Ok(InputFormat::TwoFas),
+            other => Err(anyhow::anyhow!(
+                "不支持的文件扩展名: .{}，仅支持 .json/.csv/.yaml/.yml/.2fas（可附加 .gz，或通过 --input-format 指定）",
+                other
+            )),
+        }
+    }
+}
+
+/// 读取并合并所有输入文件（支持通配符），重名问题交给后续的文件名去重处理
+pub fn load(args: &InputArgs) -> Result<TotpExport> {
+    let paths = expand_inputs(&args.input)?;
+    let mut merged: Option<TotpExport> = None;
+    for path in &paths {
+        let mut export = load_one(path, args)?;
+        resolve_secret_files(path, &mut export, args.allow_absolute_secrets)?;
+        check_total(path, &export, args.strict)?;
+        check_export_time(path, &export, args.strict)?;
+        if paths.len() > 1 {
+            info!("   └─ {} 项", export.entries.len());
+        }
+        merged = Some(match merged {
+            None => export,
+            Some(mut merged) => {
+                // 任一文件未声明条目数时，合并后的总数也无从得知
+                merged.total_entries = merged.total_entries.zip(export.total_entries).map(|(a, b)| a + b);
+                // 多个文件的导出时间不一致时无法用单个值表示
+                if merged.export_time != export.export_time {
+                    merged.export_time = None;
+                }
+                merged.skipped += export.skipped;
+                merged.entries.extend(export.entries);
+                merged
+            }
+        });
+    }
+    merged.ok_or_else(|| anyhow::anyhow!("没有指定任何输入文件"))
+}
+
+/// 把 `file:` 引用的相对路径换算为相对于输入文件所在目录（标准输入时为当前目录）
+///
+/// 未指定 --allow-absolute-secrets 时拒绝绝对路径和包含 `..` 的路径；远程输入不允许引用本地文件
+fn resolve_secret_files(path: &Path, export: &mut TotpExport, allow_absolute: bool) -> Result<()> {
+    let base = if path == Path::new("-") { Path::new("") } else { path.parent().unwrap_or(Path::new("")) };
+    for entry in &mut export.entries {
+        let Some(reference) = entry.secret.strip_prefix(FILE_SECRET_PREFIX) else {
+            continue;
+        };
+        if is_url(path) {
+            return Err(anyhow::anyhow!(
+                "{} ({}) 的 secret 引用了本地文件 {}，远程输入不允许读取本地文件",
+                entry.label_name, entry.username, reference
+            ));
+        }
+        let target = Path::new(reference);
+        let outside = target.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if outside && !allow_absolute {
+            return Err(anyhow::anyhow!(
+                "{} ({}) 的 secret 文件 {} 不在输入文件所在目录内，如确需读取请指定 --allow-absolute-secrets",
+                entry.label_name, entry.username, reference
+            ));
+        }
+        if target.is_relative() {
+            entry.secret = format!("{}{}", FILE_SECRET_PREFIX, base.join(target).display());
+        }
+    }
+    Ok(())
+}
+
+/// 检查文件声明的 total_entries 与实际条目数是否一致，防止截断或被篡改的导出静默生成部分结果
+fn check_total(path: &Path, export: &TotpExport, strict: bool) -> Result<()> {
+    let actual = export.entries.len();
+    let Some(total) = export.total_entries else {
+        return Ok(());
+    };
+    if total as usize == actual {
+        return Ok(());
+    }
+    let message = format!(
+        "{} 声明了 {} 个条目，实际为 {} 个，文件可能已损坏、被截断或被篡改",
+        path.display(),
+        total,
+        actual
+    );
+    if strict {
+        return Err(anyhow::anyhow!(message));
+    }
+    warn!("⚠️  {}", message);
+    Ok(())
+}
+
+/// 检查 export_time 能否按 RFC 3339 解析，未声明时跳过
+fn check_export_time(path: &Path, export: &TotpExport, strict: bool) -> Result<()> {
+    let Some(raw) = &export.export_time else {
+        return Ok(());
+    };
+    if parse_export_time(raw).is_some() {
+        return Ok(());
+    }
+    let message = format!("{} 的 export_time 不是有效的 RFC 3339 时间: {}", path.display(), raw);
+    if strict {
+        return Err(anyhow::anyhow!(message));
+    }
+    warn!("⚠️  {}", message);
+    Ok(())
+}
+
+/// 按 RFC 3339（如 2024-06-01T12:00:00Z）解析导出时间
+pub fn parse_export_time(raw: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(raw.trim()).ok()
+}
+
+/// 将导出时间转换为本地时区，并附上距今多久，便于发现过期的备份；无法解析时原样返回
+pub fn describe_export_time(raw: &str) -> String {
+    let Some(time) = parse_export_time(raw) else {
+        return raw.to_string();
+    };
+    let local = time.with_timezone(&Local);
+    let age = Local::now().signed_duration_since(local);
+    let ago = if age.num_days() > 0 {
+        format!("{} 天前", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{} 小时前", age.num_hours())
+    } else if age.num_minutes() >= 0 {
+        format!("{} 分钟前", age.num_minutes())
+    } else {
+        "晚于当前时间".to_string()
+    };
+    format!("{}（{}）", local.format("%Y-%m-%d %H:%M:%S %:z"), ago)
+}
+
+/// 展开包含 * ? [ 的通配符参数，其余路径原样保留
+pub fn expand_inputs(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        let pattern = input.to_string_lossy();
+        if is_url(input) || !pattern.contains(['*', '?', '[']) {
+            paths.push(input.clone());
+            continue;
+        }
+        let matches = glob::glob(&pattern)
+            .with_context(|| format!("无效的通配符: {}", pattern))?
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("展开通配符失败: {}", pattern))?;
+        if matches.is_empty() {
+            return Err(anyhow::anyhow!("通配符没有匹配到任何文件: {}", pattern));
+        }
+        paths.extend(matches);
+    }
+    Ok(paths)
+}
+
+/// 读取并解析单个输入，未指定格式时根据扩展名推断（标准输入与 URL 默认按 JSON 解析）
+fn load_one(input_file: &Path, args: &InputArgs) -> Result<TotpExport> {
+    let input_format = args.input_format;
+    // "-" 表示从标准输入读取，避免把密钥落盘到临时文件
+    if input_file.as_os_str() == "-" {
+        info!("📂 读取标准输入");
+        let reader = decompress(BufReader::new(io::stdin().lock()), false)?;
+        return parse(reader, input_format.unwrap_or(InputFormat::Json));
+    }
+    if is_url(input_file) {
+        let url = input_file.to_string_lossy();
+        let reader = decompress(BufReader::new(fetch(&url, args)?), is_gz(input_file))?;
+        return parse(reader, input_format.unwrap_or(InputFormat::Json))
+            .with_context(|| format!("解析地址内容失败: {}", url));
+    }
+
+    info!("📂 读取文件: {}", input_file.display());
+    let format = match input_format {
+        Some(format) => format,
+        None => InputFormat::detect(input_file)?,
+    };
+    let file = File::open(input_file)
+        .with_context(|| format!("无法读取文件: {}", input_file.display()))?;
+    let reader = decompress(BufReader::new(file), is_gz(input_file))
+        .with_context(|| format!("无法读取文件: {}", input_file.display()))?;
+    parse(reader, format)
+        .with_context(|| format!("解析文件失败: {}", input_file.display()))
+}
+
+/// gzip 数据开头的魔数
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 路径是否以 .gz 结尾
+fn is_gz(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// 按 gzip 魔数判断是否需要解压；扩展名为 .gz 但内容不是 gzip 时直接报错，避免解析出乱码
+fn decompress<'a, R: BufRead + 'a>(mut reader: R, gz_extension: bool) -> Result<Box<dyn Read + 'a>> {
+    let gzip = reader.fill_buf().context("读取输入失败")?.starts_with(&GZIP_MAGIC);
+    if gzip {
+        return Ok(Box::new(MultiGzDecoder::new(reader)));
+    }
+    if gz_extension {
+        return Err(anyhow::anyhow!("扩展名为 .gz，但内容不是 gzip 压缩数据"));
+    }
+    Ok(Box::new(reader))
+}
+
+/// 输入参数是否为 http(s):// 地址
+pub fn is_url(input: &Path) -> bool {
+    let input = input.to_string_lossy();
+    input.starts_with("https://") || input.starts_with("http://")
+}
+
+/// 请求地址并返回响应体，非 200 响应直接报错；未指定 --insecure 时拒绝明文 http
+fn fetch(url: &str, args: &InputArgs) -> Result<impl Read> {
+    if url.starts_with("http://") && !args.insecure {
+        return Err(anyhow::anyhow!("拒绝通过明文 http 读取密钥: {}，如确需使用请加 --insecure", url));
+    }
+    info!("🌐 下载: {}", url);
+    let mut client = reqwest::blocking::Client::builder();
+    if let Some(seconds) = args.timeout {
+        client = client.timeout(Duration::from_secs(seconds));
+    }
+    let response = client
+        .build()
+        .context("无法创建 HTTP 客户端")?
+        .get(url)
+        .send()
+        .with_context(|| format!("请求失败: {}", url))?;
+    let status = response.status();
+    if status != reqwest::StatusCode::OK {
+        return Err(anyhow::anyhow!("请求失败: {}，HTTP 状态码 {}", url, status));
+    }
+    Ok(response)
+}
+
+/// 输入为空或只有空白字符
+#[derive(Debug, thiserror::Error)]
+#[error("输入内容为空（或只包含空白字符）")]
+pub struct EmptyInput;
+
+/// 跳过开头的空白字符；读到结尾仍没有内容时返回 EmptyInput，避免解析器报出难懂的 EOF 错误
+fn skip_leading_whitespace<R: BufRead>(reader: &mut R) -> Result<()> {
+    loop {
+        let buf = reader.fill_buf().context("读取输入失败")?;
+        if buf.is_empty() {
+            return Err(EmptyInput.into());
+        }
+        let blank = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let done = blank < buf.len();
+        reader.consume(blank);
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// 按指定格式解析输入内容，JSON / CSV / YAML 边读边解析，不会整体读入内存
+pub fn parse<R: Read>(reader: R, format: InputFormat) -> Result<TotpExport> {
+    let mut reader = BufReader::new(reader);
+    skip_leading_whitespace(&mut reader)?;
+    let read_all = |reader: &mut BufReader<R>| -> Result<Zeroizing<String>> {
+        // 原始文本中同样包含明文 secret，解析完成后清零
+        let mut data = Zeroizing::new(String::new());
+        reader.read_to_string(&mut data).context("读取输入失败")?;
+        Ok(data)
+    };
+    match format {
+        InputFormat::Json => gen_totp_pic::parse_export_reader(reader),
+        InputFormat::Csv => parse_csv(reader),
+        InputFormat::Yaml => serde_yaml::from_reader(reader)
+            .context("YAML 解析失败，请检查文件格式是否正确"),
+        InputFormat::Aegis => aegis::parse(&read_all(&mut reader)?),
+        InputFormat::Andotp => andotp::parse(&read_all(&mut reader)?),
+        InputFormat::TwoFas => twofas::parse(&read_all(&mut reader)?),
+        InputFormat::Migration => migration::parse(&read_all(&mut reader)?),
+    }
+}
+
+fn parse_csv<R: Read>(reader: R) -> Result<TotpExport> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let mut entries = Vec::new();
+    for (index, row) in reader.deserialize::<TotpEntry>().enumerate() {
+        // 第 1 行是表头，数据从第 2 行开始
+        let entry = row.with_context(|| format!("CSV 第 {} 行解析失败", index + 2))?;
+        entries.push(entry);
+    }
+
+    Ok(TotpExport {
+        export_time: None,
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gen_totp_pic::OtpKind;
+
+    fn with_secret(secret: &str) -> TotpExport {
+        TotpExport {
+            export_time: None,
+            total_entries: None,
+            entries: vec![TotpEntry {
+                username: "alice".to_string(),
+                label_name: "GitHub".to_string(),
+                secret: secret.to_string(),
+                secret_format: None,
+                issuer: None,
+                kind: OtpKind::Totp,
+                counter: None,
+                skew: None,
+                algorithm: "SHA1".to_string(),
+                digits: 6,
+                period_time: 30,
+            }],
+            skipped: 0,
+        }
+    }
+
+    #[test]
+    fn relative_secret_files_resolve_against_input_dir() {
+        let mut export = with_secret("file:keys/github.txt");
+        resolve_secret_files(Path::new("backup/export.json"), &mut export, false).unwrap();
+        let expected = format!("{}{}", FILE_SECRET_PREFIX, Path::new("backup").join("keys/github.txt").display());
+        assert_eq!(export.entries[0].secret, expected);
+
+        // 标准输入时相对于当前目录，非 file: 的 secret 原样保留
+        let mut export = with_secret("file:github.txt");
+        resolve_secret_files(Path::new("-"), &mut export, false).unwrap();
+        assert_eq!(export.entries[0].secret, "file:github.txt");
+        let mut plain = with_secret("JBSWY3DPEHPK3PXP");
+        resolve_secret_files(Path::new("backup/export.json"), &mut plain, false).unwrap();
+        assert_eq!(plain.entries[0].secret, "JBSWY3DPEHPK3PXP");
+    }
+
+    #[test]
+    fn secret_files_outside_input_dir_are_rejected() {
+        for reference in ["file:../github.txt", "file:keys/../../github.txt", "file:/etc/github.txt"] {
+            let mut export = with_secret(reference);
+            assert!(resolve_secret_files(Path::new("backup/export.json"), &mut export, false).is_err(), "{}", reference);
+        }
+        // 远程输入即使指定 --allow-absolute-secrets 也不能引用本地文件
+        let mut export = with_secret("file:github.txt");
+        assert!(resolve_secret_files(Path::new("https://example.com/export.json"), &mut export, true).is_err());
+    }
+
+    #[test]
+    fn allow_absolute_accepts_outside_paths() {
+        let mut export = with_secret("file:/etc/github.txt");
+        resolve_secret_files(Path::new("backup/export.json"), &mut export, true).unwrap();
+        assert_eq!(export.entries[0].secret, "file:/etc/github.txt");
+
+        let mut export = with_secret("file:../github.txt");
+        resolve_secret_files(Path::new("backup/export.json"), &mut export, true).unwrap();
+        let expected = format!("{}{}", FILE_SECRET_PREFIX, Path::new("backup").join("../github.txt").display());
+        assert_eq!(export.entries[0].secret, expected);
+    }
+}
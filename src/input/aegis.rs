@@ -0,0 +1,77 @@
+//! Aegis Authenticator 明文导出格式
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+use gen_totp_pic::{OtpKind, TotpEntry, TotpExport};
+
+#[derive(Debug, Deserialize)]
+struct AegisExport {
+    db: AegisDb,
+}
+
+#[derive(Debug, Deserialize)]
+struct AegisDb {
+    entries: Vec<AegisEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AegisEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    issuer: String,
+    info: AegisInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct AegisInfo {
+    secret: String,
+    algo: String,
+    digits: u32,
+    #[serde(default = "gen_totp_pic::default_period")]
+    period: u64,
+}
+
+/// 解析 Aegis 导出，只保留 TOTP 与 Steam 条目
+pub fn parse(data: &str) -> Result<TotpExport> {
+    let export: AegisExport = serde_json::from_str(data)
+        .context("Aegis 备份解析失败，请确认导出时未加密")?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for entry in export.db.entries {
+        let algorithm = match entry.kind.as_str() {
+            "totp" => entry.info.algo,
+            "steam" => "STEAM".to_string(),
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        entries.push(TotpEntry {
+            username: entry.name,
+            label_name: entry.issuer,
+            secret: entry.info.secret,
+            secret_format: None,
+            issuer: None,
+            kind: OtpKind::Totp,
+            counter: None,
+            skew: None,
+            algorithm,
+            digits: entry.info.digits,
+            period_time: entry.info.period,
+        });
+    }
+    if skipped > 0 {
+        warn!("⚠️  已跳过 {} 个非 TOTP 条目（如 HOTP）", skipped);
+    }
+
+    Ok(TotpExport {
+        export_time: None,
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped,
+    })
+}
@@ -0,0 +1,80 @@
+//! andOTP 明文 JSON 备份格式
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+use gen_totp_pic::{OtpKind, TotpEntry, TotpExport};
+
+#[derive(Debug, Deserialize)]
+struct AndOtpEntry {
+    secret: String,
+    label: String,
+    #[serde(default)]
+    issuer: String,
+    algorithm: String,
+    digits: u32,
+    #[serde(default = "gen_totp_pic::default_period")]
+    period: u64,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// 解析 andOTP 备份，只保留 TOTP 与 Steam 条目
+pub fn parse(data: &str) -> Result<TotpExport> {
+    let backup: Vec<AndOtpEntry> = serde_json::from_str(data)
+        .context("andOTP 备份解析失败，请确认导出时未加密")?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for entry in backup {
+        let algorithm = match entry.kind.to_uppercase().as_str() {
+            "TOTP" => entry.algorithm,
+            "STEAM" => "STEAM".to_string(),
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let (label_name, username) = split_label(&entry.label, &entry.issuer);
+        entries.push(TotpEntry {
+            username,
+            label_name,
+            secret: entry.secret,
+            secret_format: None,
+            issuer: None,
+            kind: OtpKind::Totp,
+            counter: None,
+            skew: None,
+            algorithm,
+            digits: entry.digits,
+            period_time: entry.period,
+        });
+    }
+    if skipped > 0 {
+        warn!("⚠️  已跳过 {} 个非 TOTP 条目（如 HOTP）", skipped);
+    }
+
+    Ok(TotpExport {
+        export_time: None,
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped,
+    })
+}
+
+/// andOTP 的 label 常为 `Issuer:account`，按第一个冒号拆分；issuer 字段非空时优先使用
+fn split_label(label: &str, issuer: &str) -> (String, String) {
+    let (prefix, account) = match label.split_once(':') {
+        Some((prefix, account)) => (prefix.trim(), account.trim()),
+        None => ("", label.trim()),
+    };
+    let label_name = if !issuer.is_empty() {
+        issuer
+    } else if !prefix.is_empty() {
+        prefix
+    } else {
+        account
+    };
+    (label_name.to_string(), account.to_string())
+}
@@ -0,0 +1,90 @@
+//! 2FAS Auth 明文 JSON 备份格式（.2fas）
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+use gen_totp_pic::{OtpKind, TotpEntry, TotpExport};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TwoFasBackup {
+    #[serde(default)]
+    services: Vec<TwoFasService>,
+    /// 设置了备份密码时，条目加密后存放在这里，services 为空
+    services_encrypted: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwoFasService {
+    name: String,
+    secret: String,
+    otp: TwoFasOtp,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TwoFasOtp {
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    account: String,
+    issuer: Option<String>,
+    #[serde(default = "gen_totp_pic::default_digits")]
+    digits: u32,
+    #[serde(default = "gen_totp_pic::default_period")]
+    period: u64,
+    #[serde(default = "gen_totp_pic::default_algorithm")]
+    algorithm: String,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+}
+
+fn default_token_type() -> String {
+    "TOTP".to_string()
+}
+
+/// 解析 2FAS 备份，只保留 TOTP 条目；服务名称作为 label_name，账户名缺失时退回 otp.label
+pub fn parse(data: &str) -> Result<TotpExport> {
+    let backup: TwoFasBackup = serde_json::from_str(data)
+        .context("2FAS 备份解析失败，请确认导出时未设置备份密码")?;
+    if backup.services.is_empty() && backup.services_encrypted.is_some() {
+        return Err(anyhow::anyhow!("2FAS 备份已加密，请在 2FAS 中关闭备份密码后重新导出"));
+    }
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for service in backup.services {
+        if !service.otp.token_type.eq_ignore_ascii_case("TOTP") {
+            skipped += 1;
+            continue;
+        }
+        let otp = service.otp;
+        let username = if otp.account.is_empty() { otp.label } else { otp.account };
+        // issuer 与服务名称不同时才单独记录，避免重复
+        let issuer = otp.issuer.filter(|issuer| !issuer.is_empty() && *issuer != service.name);
+        entries.push(TotpEntry {
+            username,
+            label_name: service.name,
+            secret: service.secret,
+            secret_format: None,
+            issuer,
+            kind: OtpKind::Totp,
+            counter: None,
+            skew: None,
+            algorithm: otp.algorithm,
+            digits: otp.digits,
+            period_time: otp.period,
+        });
+    }
+    if skipped > 0 {
+        warn!("⚠️  已跳过 {} 个非 TOTP 条目（如 HOTP、Steam）", skipped);
+    }
+
+    Ok(TotpExport {
+        export_time: None,
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped,
+    })
+}
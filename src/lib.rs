@@ -0,0 +1,734 @@
+//! 将 TOTP 导出数据转换为二维码与 otpauth 链接
+//!
+//! ```no_run
+//! let data = std::fs::read_to_string("totp.json")?;
+//! let export = gen_totp_pic::parse_export(&data)?;
+//! for entry in &export.entries {
+//!     let png = gen_totp_pic::generate_qr(entry)?;
+//!     std::fs::write(format!("{}.png", gen_totp_pic::sanitize(&entry.label_name)), png)?;
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::{io::Read, sync::{atomic::{AtomicBool, Ordering}, OnceLock}};
+use totp_rs::{Algorithm, Secret, TOTP};
+use zeroize::{Zeroize, Zeroizing};
+
+/// JSON 根对象结构
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TotpExport {
+    pub export_time: Option<String>,   // CSV 等格式没有导出时间
+    #[serde(default)]
+    pub total_entries: Option<u32>,    // 部分导出省略该字段
+    pub entries: Vec<TotpEntry>,
+    /// 从其他应用导入时跳过的非 TOTP 条目数，计入运行汇总，不参与序列化
+    #[serde(skip)]
+    pub skipped: usize,
+}
+
+/// TOTP 条目结构，省略的 algorithm/digits/period_time 取标准值 SHA1/6/30
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TotpEntry {
+    pub username: String,
+    pub label_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,   // 省略时用 label_name 充当 issuer
+    pub secret: String,      // Base32 字符串（或十六进制，见 secret_format）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_format: Option<SecretFormat>,   // 省略时先按 Base32、再按十六进制尝试
+    #[serde(default, rename = "type", skip_serializing_if = "OtpKind::is_totp")]
+    pub kind: OtpKind,                          // "totp"（默认）或 "hotp"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u64>,                   // 仅 HOTP 使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skew: Option<u8>,                       // 允许前后各偏差几个周期，省略时为 1
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,   // "SHA1" / "SHA256" / ...
+    #[serde(default = "default_digits")]
+    pub digits: u32,
+    #[serde(default = "default_period")]
+    pub period_time: u64,    // 秒
+}
+
+/// 一次性密码类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpKind {
+    /// 基于时间
+    #[default]
+    Totp,
+    /// 基于计数器
+    Hotp,
+}
+
+impl OtpKind {
+    fn is_totp(&self) -> bool {
+        *self == OtpKind::Totp
+    }
+}
+
+/// secret 的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretFormat {
+    Base32,
+    Hex,
+}
+
+impl TotpEntry {
+    /// 链接中使用的 issuer：优先使用 issuer 字段，否则退回 label_name
+    pub fn issuer(&self) -> &str {
+        self.issuer.as_deref().unwrap_or(&self.label_name)
+    }
+}
+
+/// 条目释放时清零 secret，避免明文种子残留在已释放的内存中
+impl Drop for TotpEntry {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// 省略 algorithm 时的标准值，各导入格式共用
+pub fn default_algorithm() -> String {
+    "SHA1".to_string()
+}
+
+/// 省略 digits 时的标准值
+pub fn default_digits() -> u32 {
+    6
+}
+
+/// 省略周期时的标准值（秒）
+pub fn default_period() -> u64 {
+    30
+}
+
+/// 解析本工具的 JSON 导出格式
+pub fn parse_export(data: &str) -> Result<TotpExport> {
+    serde_json::from_str(data)
+        .context("JSON 解析失败，请检查文件格式是否正确")
+}
+
+/// 从 reader 流式解析 JSON 导出，无需先把整个文件读入内存
+pub fn parse_export_reader<R: Read>(reader: R) -> Result<TotpExport> {
+    serde_json::from_reader(reader)
+        .context("JSON 解析失败，请检查文件格式是否正确")
+}
+
+/// totp-rs 内置 PNG 渲染使用的纠错等级
+const TOTP_RS_ECC: QrCodeEcc = QrCodeEcc::Medium;
+
+/// 版本 40 二维码在指定纠错等级下以字节模式最多能容纳的字节数，由 qrcodegen 实际编码得出并缓存
+pub fn qr_max_bytes(ecc: QrCodeEcc) -> usize {
+    static CACHE: [OnceLock<usize>; 4] = [const { OnceLock::new() }; 4];
+    let index = match ecc {
+        QrCodeEcc::Low => 0,
+        QrCodeEcc::Medium => 1,
+        QrCodeEcc::Quartile => 2,
+        QrCodeEcc::High => 3,
+    };
+    *CACHE[index].get_or_init(|| {
+        // 能否编码随长度单调变化，二分查找最长的可编码长度
+        let (mut fits, mut too_long) = (0, 4096);
+        while too_long - fits > 1 {
+            let mid = (fits + too_long) / 2;
+            if QrCode::encode_binary(&vec![0; mid], ecc).is_ok() {
+                fits = mid;
+            } else {
+                too_long = mid;
+            }
+        }
+        fits
+    })
+}
+
+/// 为单个条目生成二维码 PNG（仅支持 TOTP，HOTP 请用 [`build_hotp_url`] 自行渲染）
+pub fn generate_qr(entry: &TotpEntry) -> Result<Vec<u8>> {
+    let totp = build_totp(entry)?;
+    let len = Zeroizing::new(totp.get_url()).len();
+    let max = qr_max_bytes(TOTP_RS_ECC);
+    if len > max {
+        return Err(anyhow::anyhow!(
+            "链接长度为 {} 字节，超过二维码的最大容量 {} 字节，请缩短服务名称或账户名",
+            len, max
+        ));
+    }
+    totp.get_qr_png()
+        .map_err(|e| anyhow::anyhow!("生成二维码失败: {}", e))
+}
+
+/// 未设置 skew 时允许前后各偏差 1 个周期
+pub const DEFAULT_SKEW: u8 = 1;
+/// skew 上限，过大的窗口会让过期验证码长时间有效
+pub const MAX_SKEW: u8 = 10;
+
+/// 构建 TOTP 失败的原因，便于库的调用方按类型处理
+#[derive(Debug, thiserror::Error)]
+pub enum TotpBuildError {
+    /// 条目类型为 HOTP，应改用 [`build_hotp_url`]
+    #[error("HOTP 条目无法构建 TOTP，请使用 build_hotp_url")]
+    Hotp,
+    /// 算法不是 SHA1/SHA256/SHA512/STEAM
+    #[error("不支持的算法: {0}，仅支持 SHA1/SHA256/SHA512/STEAM（不区分大小写，可写作 sha-1、SHA_256、HmacSHA512 等）")]
+    UnsupportedAlgorithm(String),
+    /// digits 不在 6-8 之间
+    #[error("digits 必须在 6-8 之间，当前值: {0}")]
+    InvalidDigits(u32),
+    /// period_time 为 0
+    #[error("period_time 不能为 0")]
+    ZeroPeriod,
+    /// skew 超过 [`MAX_SKEW`]
+    #[error("skew 不能大于 {MAX_SKEW}，当前值: {0}")]
+    InvalidSkew(u8),
+    /// `env:` / `file:` 引用无法解析，内容为详细原因
+    #[error("{0}")]
+    SecretReference(String),
+    /// secret 不是有效的 Base32 / 十六进制，内容为已脱敏的详细原因
+    #[error("{0}")]
+    SecretDecode(String),
+    /// 解码后的 secret 短于要求的最短长度
+    #[error("secret 仅 {len} 字节，短于 {min} 字节，可能输入有误")]
+    SecretTooShort { len: usize, min: usize },
+    /// 服务名称或账户名含有冒号或控制字符
+    #[error("{field} 含有冒号或控制字符: {value:?}，链接中会被百分号编码，但部分验证器 App 仍会错误拆分服务名称与账户名")]
+    UnsafeLabel { field: &'static str, value: String },
+}
+
+/// RFC 4226 建议的 secret 最短长度（字节，即 80 位）
+pub const MIN_SECRET_LEN: usize = 10;
+
+/// 检查解码后的 secret 长度，过短的 secret 多半是抄错或截断
+pub fn check_secret_len(len: usize, min: usize) -> Result<(), TotpBuildError> {
+    if len < min {
+        return Err(TotpBuildError::SecretTooShort { len, min });
+    }
+    Ok(())
+}
+
+/// 检查服务名称、issuer 和账户名：otpauth 标签以冒号分隔 issuer 与账户名，二者本身不能含有冒号
+///
+/// [`build_totp`] 生成的链接会对这些字符做百分号编码，结构本身不会被破坏，这里只负责发现问题
+pub fn check_label(entry: &TotpEntry) -> Result<(), TotpBuildError> {
+    let fields = [
+        ("label_name", Some(&entry.label_name)),
+        ("issuer", entry.issuer.as_ref()),
+        ("username", Some(&entry.username)),
+    ];
+    for (field, value) in fields {
+        if let Some(value) = value
+            && value.chars().any(|c| c == ':' || c.is_control())
+        {
+            return Err(TotpBuildError::UnsafeLabel { field, value: value.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// 规范化算法名：转为大写，去掉 `-`、`_` 分隔符和 `HMAC` 前缀，如 `HmacSHA-256` 转为 `SHA256`
+pub fn normalize_algorithm(raw: &str) -> String {
+    let name: String = raw
+        .trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect::<String>()
+        .to_uppercase();
+    match name.strip_prefix("HMAC") {
+        Some(rest) => rest.to_string(),
+        None => name,
+    }
+}
+
+/// 将条目中的算法、secret 等转换为 TOTP
+///
+/// 使用 `new_unchecked` 构建，不强制 128 位 secret 下限
+pub fn build_totp(entry: &TotpEntry) -> Result<TOTP, TotpBuildError> {
+    if entry.kind == OtpKind::Hotp {
+        return Err(TotpBuildError::Hotp);
+    }
+
+    // 解析算法
+    let algorithm = match normalize_algorithm(&entry.algorithm).as_str() {
+        "SHA1" => Algorithm::SHA1,
+        "SHA256" => Algorithm::SHA256,
+        "SHA512" => Algorithm::SHA512,
+        // Steam Guard 使用 5 位字母验证码，位数与周期固定
+        "STEAM" => Algorithm::Steam,
+        _ => return Err(TotpBuildError::UnsupportedAlgorithm(entry.algorithm.clone())),
+    };
+    
+    // 验证参数（Steam 固定为 5 位、30 秒，无需校验）
+    if algorithm != Algorithm::Steam {
+        if entry.digits < 6 || entry.digits > 8 {
+            return Err(TotpBuildError::InvalidDigits(entry.digits));
+        }
+        
+        if entry.period_time == 0 {
+            return Err(TotpBuildError::ZeroPeriod);
+        }
+    }
+    let skew = entry.skew.unwrap_or(DEFAULT_SKEW);
+    if skew > MAX_SKEW {
+        return Err(TotpBuildError::InvalidSkew(skew));
+    }
+    
+    let raw = resolve_secret(&entry.secret)
+        .map_err(|e| TotpBuildError::SecretReference(format!("{:#}", e)))?;
+    let secret_bytes = decode_resolved(entry, &raw)
+        .map_err(|e| TotpBuildError::SecretDecode(format!("{:#}", e)))?;
+    
+    // Steam 链接为 otpauth://steam/...，issuer 固定为 Steam
+    if algorithm == Algorithm::Steam {
+        let mut totp = TOTP::new_steam(secret_bytes.to_vec(), entry.username.clone());
+        totp.skew = skew;
+        return Ok(totp);
+    }
+    
+    // 创建 TOTP - 使用 new_unchecked 绕过 128 位限制，保持原始 secret 不变
+    let totp = TOTP::new_unchecked(
+        algorithm,
+        entry.digits as usize,
+        skew,
+        entry.period_time,
+        secret_bytes.to_vec(),
+        Some(entry.issuer().to_string()),
+        entry.username.clone(),
+    );
+    
+    Ok(totp)
+}
+
+/// 将 HOTP 条目转换为以计数器代替时间的生成器，返回生成器与计数器
+///
+/// 周期设为 1 秒后，以计数器作为时间戳计算即为 HOTP；period_time 对 HOTP 无意义，不做校验
+fn build_hotp(entry: &TotpEntry) -> Result<(TOTP, u64)> {
+    let counter = entry.counter
+        .ok_or_else(|| anyhow::anyhow!("HOTP 条目缺少 counter"))?;
+    let algorithm = match normalize_algorithm(&entry.algorithm).as_str() {
+        "SHA1" => Algorithm::SHA1,
+        "SHA256" => Algorithm::SHA256,
+        "SHA512" => Algorithm::SHA512,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "HOTP 不支持的算法: {}，仅支持 SHA1/SHA256/SHA512（不区分大小写，可带 - / _ 分隔符或 Hmac 前缀）", 
+                entry.algorithm
+            ));
+        }
+    };
+    if entry.digits < 6 || entry.digits > 8 {
+        return Err(anyhow::anyhow!(
+            "digits 必须在 6-8 之间，当前值: {}", 
+            entry.digits
+        ));
+    }
+    let secret_bytes = decode_secret(entry)?;
+    let generator = TOTP::new_unchecked(
+        algorithm,
+        entry.digits as usize,
+        0,
+        1,
+        secret_bytes.to_vec(),
+        Some(entry.issuer().to_string()),
+        entry.username.clone(),
+    );
+    Ok((generator, counter))
+}
+
+/// 生成 HOTP 条目的 otpauth://hotp 链接，包含全部参数
+pub fn build_hotp_url(entry: &TotpEntry) -> Result<String> {
+    let (generator, counter) = build_hotp(entry)?;
+    let secret = Zeroizing::new(generator.get_secret_base32());
+    let issuer = url_encode(entry.issuer());
+    Ok(format!(
+        "otpauth://hotp/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&counter={}",
+        issuer,
+        url_encode(&entry.username),
+        secret.as_str(),
+        issuer,
+        generator.algorithm,
+        generator.digits,
+        counter
+    ))
+}
+
+/// 计算 HOTP 条目当前计数器对应的验证码
+pub fn hotp_code(entry: &TotpEntry) -> Result<String> {
+    let (generator, counter) = build_hotp(entry)?;
+    Ok(generator.generate(counter))
+}
+
+/// 与 totp-rs 一致的百分号编码：只保留字母数字和 `-._~`
+fn url_encode(raw: &str) -> String {
+    raw.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 按 secret_format 解码 secret；未指定时先尝试 Base32，失败后再尝试十六进制
+///
+/// 解码前去掉所有空白并转为大写，兼容手工编辑时留下的空格和小写；报错时仍显示原始值
+///
+/// 返回的字节以及中间结果在释放时清零
+pub fn decode_secret(entry: &TotpEntry) -> Result<Zeroizing<Vec<u8>>> {
+    decode_resolved(entry, &resolve_secret(&entry.secret)?)
+}
+
+/// 按条目的 secret_format 解码已解析引用的 secret 原文
+fn decode_resolved(entry: &TotpEntry, raw: &str) -> Result<Zeroizing<Vec<u8>>> {
+    // 引用本身不含 secret，报错时原样显示
+    let shown = if is_secret_reference(&entry.secret) { entry.secret.clone() } else { redact(&entry.secret) };
+    let mut normalized: Zeroizing<String> = Zeroizing::new(raw
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect());
+    normalized.make_ascii_uppercase();
+    let base32 = || Secret::Encoded(normalized.to_string())
+        .to_bytes()
+        .map(Zeroizing::new)
+        .with_context(|| format!(
+            "Base32 解码失败，请检查 secret 格式: {}", 
+            shown
+        ));
+    let hex = || decode_hex(&normalized)
+        .with_context(|| format!(
+            "十六进制解码失败，请检查 secret 格式: {}", 
+            shown
+        ));
+    match entry.secret_format {
+        Some(SecretFormat::Base32) => base32(),
+        Some(SecretFormat::Hex) => hex(),
+        None => base32().or_else(|e| hex().map_err(|_| e.context(
+            "secret 既不是有效的 Base32，也不是有效的十六进制"
+        ))),
+    }
+}
+
+/// secret 字段以该前缀开头时，从同名环境变量读取实际的 secret
+pub const ENV_SECRET_PREFIX: &str = "env:";
+
+/// secret 字段以该前缀开头时，从该路径的文件读取实际的 secret
+pub const FILE_SECRET_PREFIX: &str = "file:";
+
+/// secret 字段是否为引用（如 `env:NAME`、`file:PATH`）而不是 secret 本身
+pub fn is_secret_reference(secret: &str) -> bool {
+    secret.starts_with(ENV_SECRET_PREFIX) || secret.starts_with(FILE_SECRET_PREFIX)
+}
+
+/// 解析 secret 引用：`env:NAME` 读取环境变量 NAME，`file:PATH` 读取文件并去掉末尾的空白，其余值原样返回
+///
+/// `file:` 的相对路径按当前目录解析，命令行会在读取输入时将其换算为相对于输入文件所在目录
+pub fn resolve_secret(secret: &str) -> Result<Zeroizing<String>> {
+    if let Some(path) = secret.strip_prefix(FILE_SECRET_PREFIX) {
+        let content = Zeroizing::new(std::fs::read_to_string(path)
+            .with_context(|| format!("无法读取 secret 文件: {}", path))?);
+        return Ok(Zeroizing::new(content.trim_end().to_string()));
+    }
+    let Some(name) = secret.strip_prefix(ENV_SECRET_PREFIX) else {
+        return Ok(Zeroizing::new(secret.to_string()));
+    };
+    match std::env::var(name) {
+        Ok(value) => Ok(Zeroizing::new(value)),
+        Err(std::env::VarError::NotPresent) => Err(anyhow::anyhow!("环境变量 {} 未设置（secret 引用 {}）", name, secret)),
+        Err(std::env::VarError::NotUnicode(_)) => Err(anyhow::anyhow!("环境变量 {} 不是有效的 UTF-8（secret 引用 {}）", name, secret)),
+    }
+}
+
+/// 解码十六进制字符串，长度必须为偶数
+fn decode_hex(raw: &str) -> Result<Zeroizing<Vec<u8>>> {
+    if raw.is_empty() || !raw.len().is_multiple_of(2) {
+        return Err(anyhow::anyhow!("十六进制长度必须为非零偶数，当前长度: {}", raw.len()));
+    }
+    if let Some(bad) = raw.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("包含无效的十六进制字符: {:?}", bad));
+    }
+    Ok(Zeroizing::new((0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&raw[i..i + 2], 16).expect("已校验为十六进制"))
+        .collect()))
+}
+
+/// 是否在日志和错误信息中显示完整 secret，仅用于调试
+static SHOW_SECRETS: AtomicBool = AtomicBool::new(false);
+
+/// 设置是否在日志和错误信息中显示完整 secret
+pub fn set_show_secrets(show: bool) {
+    SHOW_SECRETS.store(show, Ordering::Relaxed);
+}
+
+/// 遮蔽 secret，只保留前两个字符和长度，如 `JB***（共 16 字符）`
+pub fn redact(secret: &str) -> String {
+    if SHOW_SECRETS.load(Ordering::Relaxed) {
+        return secret.to_string();
+    }
+    let prefix: String = secret.chars().take(2).collect();
+    format!("{}***（共 {} 字符）", prefix, secret.chars().count())
+}
+
+/// 遮蔽 otpauth 链接中的 secret 参数，其余部分保持不变
+pub fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let params: Vec<String> = query
+        .split('&')
+        .map(|param| match param.strip_prefix("secret=") {
+            Some(secret) => format!("secret={}", redact(secret)),
+            None => param.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// 清洗文件名：先将非 ASCII 字符转写为 ASCII（如 `銀行` → `YinXing`），
+/// 再只保留字母数字以及 `-`、`_`
+///
+/// 清洗后为空时返回基于原文哈希的 `x` + 8 位十六进制，保证不会生成 `-user.png` 之类的文件名
+pub fn sanitize(raw: &str) -> String {
+    let cleaned: String = deunicode::deunicode(raw)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if !cleaned.is_empty() {
+        return cleaned;
+    }
+    let digest = Sha256::digest(raw.as_bytes());
+    let hash: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("x{}", hash)
+}
+
+/// secret 的指纹：解码后字节的 SHA-256 前 8 位十六进制，可用于比对两份导出而不泄露 secret
+pub fn secret_fingerprint(secret: &[u8]) -> String {
+    Sha256::digest(secret)[..4].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(secret: &str) -> TotpEntry {
+        TotpEntry {
+            username: "alice".to_string(),
+            label_name: "GitHub".to_string(),
+            secret: secret.to_string(),
+            secret_format: None,
+            issuer: None,
+            kind: OtpKind::Totp,
+            counter: None,
+            skew: None,
+            algorithm: default_algorithm(),
+            digits: default_digits(),
+            period_time: default_period(),
+        }
+    }
+
+    #[test]
+    fn secret_with_spaces_is_accepted() {
+        let expected = build_totp(&entry("JBSWY3DPEHPK3PXP")).unwrap().secret.clone();
+        assert_eq!(build_totp(&entry("JBSW Y3DP EHPK 3PXP")).unwrap().secret, expected);
+        assert_eq!(build_totp(&entry(" JBSWY3DP\tEHPK3PXP\n")).unwrap().secret, expected);
+    }
+
+    #[test]
+    fn lowercase_secret_is_accepted() {
+        let expected = build_totp(&entry("JBSWY3DPEHPK3PXP")).unwrap().secret.clone();
+        assert_eq!(build_totp(&entry("jbswy3dpehpk3pxp")).unwrap().secret, expected);
+        assert_eq!(build_totp(&entry("jbsw y3dp EHPK 3pxp")).unwrap().secret, expected);
+    }
+
+    #[test]
+    fn issuer_defaults_to_label() {
+        let url = build_totp(&entry("JBSWY3DPEHPK3PXP")).unwrap().get_url();
+        assert!(url.starts_with("otpauth://totp/GitHub:alice?"));
+        assert!(url.contains("issuer=GitHub"));
+    }
+
+    #[test]
+    fn explicit_issuer_is_used_in_url() {
+        let mut entry = entry("JBSWY3DPEHPK3PXP");
+        entry.issuer = Some("Microsoft".to_string());
+        let url = build_totp(&entry).unwrap().get_url();
+        assert!(url.starts_with("otpauth://totp/Microsoft:alice?"));
+        assert!(url.contains("issuer=Microsoft"));
+    }
+
+    #[test]
+    fn hotp_matches_rfc4226_vectors() {
+        // RFC 4226 附录 D：secret 为 "12345678901234567890"
+        let mut entry = entry("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        entry.kind = OtpKind::Hotp;
+        for (counter, expected) in [(0, "755224"), (1, "287082"), (9, "520489")] {
+            entry.counter = Some(counter);
+            assert_eq!(hotp_code(&entry).unwrap(), expected);
+        }
+        assert!(build_hotp_url(&entry).unwrap().starts_with("otpauth://hotp/GitHub:alice?"));
+    }
+
+    #[test]
+    fn invalid_secret_error_is_redacted() {
+        let err = format!("{:#}", build_totp(&entry("jbsw !!!")).unwrap_err());
+        assert!(err.contains("jb***（共 8 字符）"));
+        assert!(!err.contains("jbsw !!!"));
+    }
+
+    #[test]
+    fn build_totp_accepts_supported_algorithms() {
+        for (name, expected) in [("SHA1", Algorithm::SHA1), ("sha256", Algorithm::SHA256), ("Sha512", Algorithm::SHA512)] {
+            let mut valid = entry("JBSWY3DPEHPK3PXP");
+            valid.algorithm = name.to_string();
+            valid.digits = 8;
+            valid.period_time = 60;
+            let totp = build_totp(&valid).unwrap();
+            assert_eq!(totp.algorithm, expected);
+            assert_eq!(totp.digits, 8);
+            assert_eq!(totp.step, 60);
+            assert_eq!(totp.account_name, "alice");
+            assert_eq!(totp.issuer.as_deref(), Some("GitHub"));
+        }
+    }
+
+    #[test]
+    fn algorithm_aliases_are_normalized() {
+        for (name, expected) in [("sha-1", Algorithm::SHA1), ("SHA_256", Algorithm::SHA256), ("HmacSHA512", Algorithm::SHA512)] {
+            let mut custom = entry("JBSWY3DPEHPK3PXP");
+            custom.algorithm = name.to_string();
+            assert_eq!(build_totp(&custom).unwrap().algorithm, expected, "{}", name);
+        }
+        let mut custom = entry("JBSWY3DPEHPK3PXP");
+        custom.algorithm = "MD5".to_string();
+        assert!(matches!(build_totp(&custom), Err(TotpBuildError::UnsupportedAlgorithm(name)) if name == "MD5"));
+    }
+
+    #[test]
+    fn build_totp_checks_digit_range() {
+        let mut custom = entry("JBSWY3DPEHPK3PXP");
+        for digits in [5, 9] {
+            custom.digits = digits;
+            assert!(matches!(build_totp(&custom), Err(TotpBuildError::InvalidDigits(d)) if d == digits));
+        }
+        for digits in 6..=8 {
+            custom.digits = digits;
+            assert!(build_totp(&custom).is_ok());
+        }
+    }
+
+    #[test]
+    fn env_secret_reference_must_be_set() {
+        let err = decode_secret(&entry("env:GEN_TOTP_PIC_TEST_UNSET")).unwrap_err();
+        assert!(format!("{:#}", err).contains("环境变量 GEN_TOTP_PIC_TEST_UNSET 未设置"));
+    }
+
+    #[test]
+    fn qr_capacity_matches_version_40() {
+        assert_eq!(qr_max_bytes(QrCodeEcc::Low), 2953);
+        assert_eq!(qr_max_bytes(QrCodeEcc::Medium), 2331);
+        assert_eq!(qr_max_bytes(QrCodeEcc::High), 1273);
+    }
+
+    #[test]
+    fn short_secrets_are_flagged() {
+        let short = build_totp(&entry("JBSWY3DP")).unwrap();
+        assert!(matches!(check_secret_len(short.secret.len(), MIN_SECRET_LEN),
+                         Err(TotpBuildError::SecretTooShort { len: 5, min: 10 })));
+        let normal = build_totp(&entry("JBSWY3DPEHPK3PXP")).unwrap();
+        assert!(check_secret_len(normal.secret.len(), MIN_SECRET_LEN).is_ok());
+    }
+
+    #[test]
+    fn colon_labels_are_flagged_and_encoded() {
+        let mut colon = entry("JBSWY3DPEHPK3PXP");
+        colon.label_name = "Work:GitHub".to_string();
+        assert!(matches!(check_label(&colon),
+                         Err(TotpBuildError::UnsafeLabel { field: "label_name", .. })));
+        let url = build_totp(&colon).unwrap().get_url();
+        assert!(url.starts_with("otpauth://totp/Work%3AGitHub:alice?"), "{}", url);
+        assert!(check_label(&entry("JBSWY3DPEHPK3PXP")).is_ok());
+    }
+
+    #[test]
+    fn build_errors_can_be_matched() {
+        let mut bad = entry("JBSWY3DPEHPK3PXP");
+        bad.digits = 9;
+        assert!(matches!(build_totp(&bad), Err(TotpBuildError::InvalidDigits(9))));
+        bad.digits = 6;
+        bad.period_time = 0;
+        assert!(matches!(build_totp(&bad), Err(TotpBuildError::ZeroPeriod)));
+        bad.algorithm = "MD5".to_string();
+        assert!(matches!(build_totp(&bad), Err(TotpBuildError::UnsupportedAlgorithm(a)) if a == "MD5"));
+        assert!(matches!(build_totp(&entry("!!!")), Err(TotpBuildError::SecretDecode(_))));
+        assert!(matches!(build_totp(&entry("env:GEN_TOTP_PIC_TEST_UNSET")), Err(TotpBuildError::SecretReference(_))));
+    }
+
+    #[test]
+    fn skew_defaults_to_one_and_can_be_overridden() {
+        let mut custom = entry("JBSWY3DPEHPK3PXP");
+        assert_eq!(build_totp(&custom).unwrap().skew, 1);
+        custom.skew = Some(0);
+        assert_eq!(build_totp(&custom).unwrap().skew, 0);
+        custom.skew = Some(MAX_SKEW + 1);
+        assert!(matches!(build_totp(&custom), Err(TotpBuildError::InvalidSkew(_))));
+    }
+
+    #[test]
+    fn redact_url_masks_only_secret() {
+        assert_eq!(
+            redact_url("otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub"),
+            "otpauth://totp/GitHub:alice?secret=JB***（共 16 字符）&issuer=GitHub"
+        );
+    }
+
+    #[test]
+    fn sanitize_transliterates_cjk() {
+        assert_eq!(sanitize("銀行"), "YinXing");
+        assert_eq!(sanitize("支付宝-钱包"), "ZhiFuBao-QianBao");
+    }
+
+    #[test]
+    fn sanitize_strips_accents() {
+        assert_eq!(sanitize("Café"), "Cafe");
+        assert_eq!(sanitize("Crème Brûlée"), "CremeBrulee");
+    }
+
+    #[test]
+    fn sanitize_emoji_is_never_empty() {
+        let name = sanitize("🔐");
+        assert!(!name.is_empty());
+        assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn sanitize_falls_back_to_stable_hash() {
+        let name = sanitize("!!!");
+        assert_eq!(name.len(), 9);
+        assert!(name.starts_with('x'));
+        assert_eq!(name, sanitize("!!!"));
+        assert_ne!(name, sanitize("???"));
+    }
+
+    #[test]
+    fn sanitize_removes_slashes_and_spaces() {
+        assert_eq!(sanitize("Work / Mail"), "WorkMail");
+        assert_eq!(sanitize("../etc/passwd"), "etcpasswd");
+        assert_eq!(sanitize("a\\b c"), "abc");
+    }
+
+    #[test]
+    fn sanitize_empty_result_uses_fallback() {
+        for raw in ["", " / ", "..."] {
+            let name = sanitize(raw);
+            assert!(name.starts_with('x'));
+            assert_eq!(name.len(), 9);
+        }
+    }
+
+    #[test]
+    fn sanitize_keeps_ascii_unchanged() {
+        assert_eq!(sanitize("GitHub_2-fa"), "GitHub_2-fa");
+    }
+}
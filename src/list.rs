@@ -0,0 +1,29 @@
+use comfy_table::{presets::UTF8_FULL, Table};
+
+use gen_totp_pic::{is_secret_reference, redact, OtpKind, TotpExport};
+
+/// 以表格形式列出所有条目，secret 只显示脱敏后的形式
+pub fn print_entries(export: &TotpExport) {
+    let mut table = Table::new();
+    table
+        .load_style(UTF8_FULL)
+        .set_header(["#", "服务", "账户", "算法", "位数", "周期", "secret"]);
+    for (index, entry) in export.entries.iter().enumerate() {
+        // HOTP 没有周期，改为显示计数器
+        let period = match entry.kind {
+            OtpKind::Totp => format!("{} 秒", entry.period_time),
+            OtpKind::Hotp => format!("计数器 {}", entry.counter.unwrap_or_default()),
+        };
+        table.add_row([
+            (index + 1).to_string(),
+            entry.label_name.clone(),
+            entry.username.clone(),
+            entry.algorithm.clone(),
+            entry.digits.to_string(),
+            period,
+            if is_secret_reference(&entry.secret) { entry.secret.clone() } else { redact(&entry.secret) },
+        ]);
+    }
+    println!("{table}");
+    println!("共 {} 项", export.entries.len());
+}
@@ -1,162 +1,105 @@
-use serde::Deserialize;
-use totp_rs::{Algorithm, Secret, TOTP};
-use anyhow::{Context, Result};
-use std::{env, fs, path::Path};
+mod apng;
+mod archive;
+mod caption;
+mod cli;
+mod codes;
+mod config;
+mod copy;
+mod exit;
+mod gallery;
+mod generate;
+mod import;
+mod input;
+mod list;
+mod migration;
+mod montage;
+mod pdf;
+mod render;
+mod report;
+mod tui;
+mod verify;
+mod watch;
 
-/// JSON 根对象结构
-#[derive(Debug, Deserialize)]
-struct TotpExport {
-    export_time: String,
-    total_entries: u32,
-    entries: Vec<TotpEntry>,
-}
+use anyhow::Result;
+use clap::{CommandFactory, FromArgMatches};
+use std::{io::Write, process::ExitCode};
 
-/// TOTP 条目结构
-#[derive(Debug, Deserialize)]
-struct TotpEntry {
-    username: String,
-    label_name: String,
-    secret: String,      // Base32 字符串
-    algorithm: String,   // "SHA1" / "SHA256" / ...
-    digits: u32,
-    period_time: u64,    // 秒
-}
+use cli::{Cli, Command};
 
-fn main() -> Result<()> {
-    // 1. 获取输入文件名（命令行参数或默认值）
-    let args: Vec<String> = env::args().collect();
-    let input_file = if args.len() > 1 {
-        &args[1]
-    } else {
-        println!("💡 使用方法: {} <JSON文件路径>", args[0]);
-        println!("💡 或者直接运行使用默认文件: totp.json");
-        "totp.json"
-    };
-    
-    println!("📂 读取文件: {}", input_file);
-    
-    // 2. 读取 JSON 文件
-    let data = fs::read_to_string(input_file)
-        .with_context(|| format!("无法读取文件: {}", input_file))?;
-    
-    // 3. 解析 JSON 根对象
-    let export: TotpExport = serde_json::from_str(&data)
-        .context("JSON 解析失败，请检查文件格式是否正确")?;
-    
-    println!("📊 导出时间: {}", export.export_time);
-    println!("📊 总条目数: {}", export.total_entries);
-    println!("📊 实际条目数: {}", export.entries.len());
-    
-    if export.entries.is_empty() {
-        println!("⚠️  没有找到任何 TOTP 条目");
-        return Ok(());
+fn main() -> ExitCode {
+    // 1. 解析命令行参数（未指定时默认读取 totp.json），再用配置文件补齐未指定的参数
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    init_logger(cli.quiet, cli.no_emoji || std::env::var("TERM").is_ok_and(|term| term == "dumb"));
+    gen_totp_pic::set_show_secrets(cli.show_secrets);
+    let prepared = config::apply(&mut cli, &matches).and_then(|()| cli.expand_formats());
+    match prepared.and_then(|()| run(&cli)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            exit::print(&e, cli.json_errors);
+            exit::code_for(&e)
+        }
     }
+}
 
-    // 4. 创建输出目录
-    fs::create_dir_all("qr")
-        .context("无法创建 qr 目录")?;
-    fs::create_dir_all("links")
-        .context("无法创建 links 目录")?;
-    
-    // 5. 为每一项生成二维码 PNG 和 TOTP 链接
-    for (index, entry) in export.entries.iter().enumerate() {
-        println!("🔄 处理第 {}/{} 项: {} ({})", 
-                 index + 1, export.entries.len(), 
-                 entry.label_name, entry.username);
-        
-        let totp = build_totp(entry)
-            .with_context(|| format!("构建 TOTP 失败: {} ({})", entry.label_name, entry.username))?;
-        
-        // 生成二维码 PNG
-        let png = totp.get_qr_png()
-            .map_err(|e| anyhow::anyhow!("生成二维码失败: {} ({}): {}", entry.label_name, entry.username, e))?;
+/// 按子命令分发
+fn run(cli: &Cli) -> Result<()> {
+    match &cli.command {
+        Some(Command::ImportUris { input, output }) => return import::import_uris(input, output),
+        Some(Command::List { source }) => {
+            list::print_entries(&input::load(source)?);
+            return Ok(());
+        }
+        Some(Command::Codes { source, repeat }) => return codes::print_codes(&input::load(source)?, *repeat, cli.at),
+        None => {}
+    }
 
-        // 生成 TOTP 链接
-        let totp_url = totp.get_url();
-        
-        // 文件名: <label>-<username> ，去掉可能的斜杠/空格
-        let base_filename = format!(
-            "{}-{}",
-            sanitize(&entry.label_name),
-            sanitize(&entry.username)
-        );
-        
-        // 保存二维码 PNG
-        let png_path = Path::new("qr").join(format!("{}.png", base_filename));
-        fs::write(&png_path, png)
-            .with_context(|| format!("写入 PNG 文件失败: {:?}", png_path))?;
-        
-        // 保存 TOTP 链接
-        let link_path = Path::new("links").join(format!("{}.txt", base_filename));
-        fs::write(&link_path, &totp_url)
-            .with_context(|| format!("写入链接文件失败: {:?}", link_path))?;
-        
-        println!("✅ 已生成 PNG: {:?}", png_path);
-        println!("🔗 已生成链接: {:?}", link_path);
-        println!("🔗 TOTP URL: {}", totp_url);
-        println!();
+    if let Some(selector) = &cli.copy {
+        return copy::copy_entry(&input::load(&cli.source)?, selector, cli.copy_what);
     }
-    
-    println!("🎉 所有二维码和 TOTP 链接生成完成！");
-    println!("📁 二维码保存在: qr/ 目录");
-    println!("🔗 TOTP 链接保存在: links/ 目录");
-    Ok(())
+    if let Some(reference) = &cli.verify_codes {
+        return codes::verify_codes(&input::load(&cli.source)?, reference, cli.at);
+    }
+    if cli.watch {
+        return watch::watch(cli);
+    }
+    generate::run(cli)
 }
 
-/// 将 JSON 里的算法、secret 等转换为 TOTP
-fn build_totp(entry: &TotpEntry) -> Result<TOTP> {
-    // 解析算法
-    let algorithm = match entry.algorithm.to_uppercase().as_str() {
-        "SHA1" => Algorithm::SHA1,
-        "SHA256" => Algorithm::SHA256,
-        "SHA512" => Algorithm::SHA512,
-        _ => {
-            return Err(anyhow::anyhow!(
-                "不支持的算法: {}，仅支持 SHA1/SHA256/SHA512", 
-                entry.algorithm
-            ));
-        }
-    };
-    
-    // 验证参数
-    if entry.digits < 6 || entry.digits > 8 {
-        return Err(anyhow::anyhow!(
-            "digits 必须在 6-8 之间，当前值: {}", 
-            entry.digits
-        ));
+/// 初始化日志：默认 info 级别，可通过 RUST_LOG 调整；安静模式下只保留错误
+///
+/// 日志只输出消息本身，保留原有的提示风格；plain 时把开头的 emoji 换成 [INFO] 等纯 ASCII 标签
+fn init_logger(quiet: bool, plain: bool) {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if quiet {
+        builder.filter_level(log::LevelFilter::Error);
     }
-    
-    if entry.period_time == 0 {
-        return Err(anyhow::anyhow!("period_time 不能为 0"));
-    }
-    
-    // 解码 Base32 secret
-    let secret_bytes = Secret::Encoded(entry.secret.clone())
-        .to_bytes()
-        .with_context(|| format!(
-            "Base32 解码失败，请检查 secret 格式: {}", 
-            entry.secret
-        ))?;
-    
-    println!("🔑 Secret 长度: {} 字节 ({} 位)", secret_bytes.len(), secret_bytes.len() * 8);
-    
-    // 创建 TOTP - 使用 new_unchecked 绕过 128 位限制，保持原始 secret 不变
-    let totp = TOTP::new_unchecked(
-        algorithm,
-        entry.digits as usize,
-        1,  // clock skew
-        entry.period_time,
-        secret_bytes,
-        Some(entry.label_name.clone()),
-        entry.username.clone(),
-    );
-    
-    Ok(totp)
+    builder
+        .format(move |buf, record| {
+            if !plain {
+                return writeln!(buf, "{}", record.args());
+            }
+            let message = record.args().to_string();
+            if message.is_empty() {
+                return writeln!(buf);
+            }
+            // 只去掉 emoji 及其后的空格，保留缩进
+            let text = match message.trim_start_matches(is_emoji) {
+                rest if rest.len() < message.len() => rest.trim_start(),
+                _ => message.as_str(),
+            };
+            let tag = match (message.chars().next(), record.level()) {
+                (Some('✅' | '🎉'), _) => "[OK]",
+                (_, log::Level::Error) => "[ERROR]",
+                (_, log::Level::Warn) => "[WARN]",
+                _ => "[INFO]",
+            };
+            writeln!(buf, "{} {}", tag, text)
+        })
+        .init();
 }
 
-/// 简单清洗文件名
-fn sanitize(raw: &str) -> String {
-    raw.chars()
-        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
-        .collect()
-}
\ No newline at end of file
+/// 日志前缀中使用的 emoji（含变体选择符与零宽连接符）
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{2190}'..='\u{2BFF}' | '\u{1F000}'..='\u{1FAFF}' | '\u{FE0F}' | '\u{200D}')
+}
@@ -0,0 +1,286 @@
+//! Google Authenticator “转移账号”使用的 otpauth-migration:// 格式
+
+use anyhow::{Context, Result};
+use log::warn;
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use prost::Message;
+use url::Url;
+
+use gen_totp_pic::{decode_secret, normalize_algorithm, OtpKind, TotpEntry, TotpExport};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+/// 兼容带或不带 `=` 填充的 base64
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Clone, PartialEq, Message)]
+struct MigrationPayload {
+    #[prost(message, repeated, tag = "1")]
+    otp_parameters: Vec<OtpParameters>,
+    #[prost(int32, tag = "2")]
+    version: i32,
+    #[prost(int32, tag = "3")]
+    batch_size: i32,
+    #[prost(int32, tag = "4")]
+    batch_index: i32,
+    #[prost(int32, tag = "5")]
+    batch_id: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct OtpParameters {
+    #[prost(bytes = "vec", tag = "1")]
+    secret: Vec<u8>,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(string, tag = "3")]
+    issuer: String,
+    #[prost(enumeration = "MigrationAlgorithm", tag = "4")]
+    algorithm: i32,
+    #[prost(enumeration = "DigitCount", tag = "5")]
+    digits: i32,
+    #[prost(enumeration = "OtpType", tag = "6")]
+    kind: i32,
+    #[prost(int64, tag = "7")]
+    counter: i64,
+}
+
+impl Drop for OtpParameters {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+enum MigrationAlgorithm {
+    Unspecified = 0,
+    Sha1 = 1,
+    Sha256 = 2,
+    Sha512 = 3,
+    Md5 = 4,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+enum DigitCount {
+    Unspecified = 0,
+    Six = 1,
+    Eight = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+enum OtpType {
+    Unspecified = 0,
+    Hotp = 1,
+    Totp = 2,
+}
+
+/// 解析一行或多行 otpauth-migration:// 链接，只保留 TOTP 条目
+pub fn parse(data: &str) -> Result<TotpExport> {
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let payload = decode_uri(line)
+            .with_context(|| format!("第 {} 行不是有效的 otpauth-migration 链接", index + 1))?;
+        for otp in payload.otp_parameters {
+            if otp.kind() != OtpType::Totp {
+                skipped += 1;
+                continue;
+            }
+            entries.push(to_entry(otp));
+        }
+    }
+    if skipped > 0 {
+        warn!("⚠️  已跳过 {} 个非 TOTP 条目（如 HOTP）", skipped);
+    }
+
+    Ok(TotpExport {
+        export_time: None,
+        total_entries: Some(entries.len() as u32),
+        entries,
+        skipped,
+    })
+}
+
+/// 取出 data 参数，base64 解码后按 protobuf 解析
+fn decode_uri(uri: &str) -> Result<MigrationPayload> {
+    let url = Url::parse(uri).context("链接格式错误")?;
+    if url.scheme() != "otpauth-migration" {
+        return Err(anyhow::anyhow!("不支持的协议: {}", url.scheme()));
+    }
+    let data = url
+        .query_pairs()
+        .find(|(key, _)| key == "data")
+        .map(|(_, value)| value.into_owned())
+        .ok_or_else(|| anyhow::anyhow!("缺少 data 参数"))?;
+    // query_pairs 会把 + 解码成空格，这里还原
+    let bytes = BASE64
+        .decode(data.replace(' ', "+"))
+        .context("data 参数 base64 解码失败")?;
+    MigrationPayload::decode(bytes.as_slice()).context("protobuf 解析失败")
+}
+
+/// 迁移链接的打包结果
+pub struct Batches {
+    /// 每个二维码对应的链接
+    pub uris: Vec<Zeroizing<String>>,
+    /// 无法用迁移格式表示的条目序号与原因
+    pub skipped: Vec<(usize, anyhow::Error)>,
+}
+
+/// 将条目打包为一条或多条 otpauth-migration 链接
+///
+/// `fits` 判断链接能否放进一个二维码，放不下时拆分到下一批；无法用迁移格式表示的条目
+/// （如 Steam、7 位验证码、非 30 秒周期）、单独也放不进一个二维码的条目，以及未通过调用方
+/// `check`（如 --strict）的条目跳过，连同序号与原因一并返回
+pub fn encode_batches(
+    entries: &[TotpEntry],
+    fits: impl Fn(&str) -> bool,
+    check: impl Fn(&TotpEntry) -> Result<()>,
+) -> Result<Batches> {
+    let mut skipped = Vec::new();
+    let mut batches: Vec<Vec<OtpParameters>> = vec![Vec::new()];
+    for (index, entry) in entries.iter().enumerate() {
+        if let Err(e) = check(entry) {
+            skipped.push((index, e));
+            continue;
+        }
+        let otp = match to_otp(entry) {
+            Ok(otp) => otp,
+            Err(e) => {
+                skipped.push((index, e.context(format!("{} ({})", entry.label_name, entry.username))));
+                continue;
+            }
+        };
+        let batch = batches.last_mut().expect("至少有一批");
+        batch.push(otp);
+        // 按最长的批次编号估算长度，最终写入真实编号后只会更短
+        if fits(&encode_uri(batch, i32::MAX, i32::MAX, i32::MAX)) {
+            continue;
+        }
+        let otp = batch.pop().expect("刚刚放入");
+        // 单独一条也放不下时跳过该条目，不影响其他条目
+        if batch.is_empty() || !fits(&encode_uri(std::slice::from_ref(&otp), i32::MAX, i32::MAX, i32::MAX)) {
+            skipped.push((index, anyhow::anyhow!(
+                "{} ({}) 的迁移链接过长，无法放进一个二维码",
+                entry.label_name,
+                entry.username
+            )));
+            continue;
+        }
+        batches.push(vec![otp]);
+    }
+    batches.retain(|batch| !batch.is_empty());
+
+    // 同一次导出的各批次共用 batch_id，由内容计算，相同输入得到相同结果
+    let mut hasher = Sha256::new();
+    for otp in batches.iter().flatten() {
+        hasher.update(&otp.secret);
+        hasher.update(otp.name.as_bytes());
+    }
+    let digest = hasher.finalize();
+    let batch_id = i32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) & i32::MAX;
+
+    let size = batches.len() as i32;
+    let uris = batches
+        .iter()
+        .enumerate()
+        .map(|(index, batch)| encode_uri(batch, size, index as i32, batch_id))
+        .collect();
+    Ok(Batches { uris, skipped })
+}
+
+/// 将一批账号编码为 otpauth-migration://offline?data=... 链接
+fn encode_uri(batch: &[OtpParameters], batch_size: i32, batch_index: i32, batch_id: i32) -> Zeroizing<String> {
+    let payload = MigrationPayload {
+        otp_parameters: batch.to_vec(),
+        version: 1,
+        batch_size,
+        batch_index,
+        batch_id,
+    };
+    let bytes = Zeroizing::new(payload.encode_to_vec());
+    let data = Zeroizing::new(BASE64.encode(bytes.as_slice()));
+    Zeroizing::new(format!(
+        "otpauth-migration://offline?data={}",
+        url::form_urlencoded::byte_serialize(data.as_bytes()).collect::<String>()
+    ))
+}
+
+/// 将条目转换为迁移格式的账号参数
+fn to_otp(entry: &TotpEntry) -> Result<OtpParameters> {
+    let algorithm = match normalize_algorithm(&entry.algorithm).as_str() {
+        "SHA1" => MigrationAlgorithm::Sha1,
+        "SHA256" => MigrationAlgorithm::Sha256,
+        "SHA512" => MigrationAlgorithm::Sha512,
+        _ => return Err(anyhow::anyhow!("迁移格式不支持算法 {}", entry.algorithm)),
+    };
+    let digits = match entry.digits {
+        6 => DigitCount::Six,
+        8 => DigitCount::Eight,
+        other => return Err(anyhow::anyhow!("迁移格式只支持 6 位或 8 位验证码，当前为 {} 位", other)),
+    };
+    let (kind, counter) = match entry.kind {
+        OtpKind::Totp if entry.period_time != 30 => {
+            return Err(anyhow::anyhow!("迁移格式固定为 30 秒周期，当前为 {} 秒", entry.period_time));
+        }
+        OtpKind::Totp => (OtpType::Totp, 0),
+        OtpKind::Hotp => (OtpType::Hotp, entry.counter.unwrap_or_default() as i64),
+    };
+    Ok(OtpParameters {
+        secret: decode_secret(entry)?.to_vec(),
+        name: format!("{}:{}", entry.issuer(), entry.username),
+        issuer: entry.issuer().to_string(),
+        algorithm: algorithm as i32,
+        digits: digits as i32,
+        kind: kind as i32,
+        counter,
+    })
+}
+
+/// 将单个账号转换为 TOTP 条目
+fn to_entry(mut otp: OtpParameters) -> TotpEntry {
+    let algorithm = match otp.algorithm() {
+        MigrationAlgorithm::Unspecified | MigrationAlgorithm::Sha1 => "SHA1",
+        MigrationAlgorithm::Sha256 => "SHA256",
+        MigrationAlgorithm::Sha512 => "SHA512",
+        // 交给 build_totp 报出“不支持的算法”
+        MigrationAlgorithm::Md5 => "MD5",
+    };
+    let digits = match otp.digits() {
+        DigitCount::Eight => 8,
+        DigitCount::Unspecified | DigitCount::Six => 6,
+    };
+
+    // name 常为 `Issuer:account`，有 issuer 时去掉前缀
+    let username = match otp.name.split_once(':') {
+        Some((_, account)) if !otp.issuer.is_empty() => account.trim().to_string(),
+        _ => otp.name.clone(),
+    };
+    let label_name = if otp.issuer.is_empty() { std::mem::take(&mut otp.name) } else { std::mem::take(&mut otp.issuer) };
+
+    TotpEntry {
+        username,
+        label_name,
+        secret: base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &otp.secret),
+        secret_format: None,
+        issuer: None,
+        kind: OtpKind::Totp,
+        counter: None,
+        skew: None,
+        algorithm: algorithm.to_string(),
+        digits,
+        // Google Authenticator 不导出周期，固定为 30 秒
+        period_time: 30,
+    }
+}
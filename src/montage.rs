@@ -0,0 +1,54 @@
+use image::{imageops, GrayImage, Luma};
+
+use crate::caption;
+
+/// 说明文字放大倍数
+const CAPTION_SCALE: u32 = 2;
+/// 格子之间及文字周围的留白（像素）
+const PADDING: u32 = 16;
+/// 两行说明文字之间的间距（像素）
+const LINE_GAP: u32 = 4;
+
+/// 拼图中的一格：二维码及其下方的说明文字
+pub struct Tile {
+    pub image: GrayImage,
+    pub label: String,
+    pub username: String,
+}
+
+/// 将所有二维码按网格排列到一张大图上，最后一行不足时留白
+pub fn contact_sheet(tiles: &[Tile], columns: u32) -> GrayImage {
+    let columns = columns.clamp(1, tiles.len().max(1) as u32);
+    let rows = (tiles.len() as u32).div_ceil(columns);
+
+    // 各二维码版本不同尺寸也不同，统一按最大尺寸划分格子
+    let qr_size = tiles.iter().map(|t| t.image.width()).max().unwrap_or(0);
+    let line = caption::line_height(CAPTION_SCALE) + LINE_GAP;
+    let cell_width = qr_size + PADDING;
+    let cell_height = qr_size + line * 2 + PADDING * 2;
+
+    let mut sheet = GrayImage::from_pixel(
+        cell_width * columns + PADDING,
+        cell_height * rows + PADDING,
+        Luma([255]),
+    );
+
+    for (i, tile) in tiles.iter().enumerate() {
+        let col = i as u32 % columns;
+        let row = i as u32 / columns;
+        let cell_x = PADDING + col * cell_width;
+        let cell_y = PADDING + row * cell_height;
+
+        // 二维码在格子内水平居中
+        let offset = (qr_size - tile.image.width()) / 2;
+        imageops::replace(&mut sheet, &tile.image, (cell_x + offset) as i64, (cell_y + offset) as i64);
+
+        let text_y = cell_y + qr_size;
+        for (n, text) in [&tile.label, &tile.username].into_iter().enumerate() {
+            let text = caption::fit_text(text, qr_size, CAPTION_SCALE);
+            let text_x = cell_x + (qr_size - caption::text_width(&text, CAPTION_SCALE)) / 2;
+            caption::draw_text(&mut sheet, text_x, text_y + n as u32 * line, &text, CAPTION_SCALE);
+        }
+    }
+    sheet
+}
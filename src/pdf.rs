@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use printpdf::{
+    BuiltinFont, Mm, Op, PaintMode, PdfDocument, PdfFontHandle, PdfPage, PdfSaveOptions, Point,
+    Polygon, Pt, Rect, TextItem, WindingOrder,
+};
+use std::{fs, path::Path};
+use zeroize::Zeroizing;
+
+use crate::render::{self, RenderOptions};
+
+/// A4 页面尺寸（毫米）
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+/// 二维码边长（毫米，含留白）
+const QR_SIZE: f32 = 120.0;
+/// 页面左右边距（毫米）
+const PAGE_MARGIN: f32 = 20.0;
+/// 链接文字每行的字符数，Courier 7pt 时约占满版心
+const URL_LINE_CHARS: usize = 100;
+
+/// PDF 中的一页：一个二维码及其说明
+pub struct Page {
+    /// 实际写入链接的 issuer（未单独指定时即服务名称）
+    pub issuer: String,
+    pub label: String,
+    pub username: String,
+    pub url: Zeroizing<String>,
+}
+
+/// 生成每页一个二维码的 PDF，上方印 issuer、服务名与账户名，下方以小字印出 otpauth 链接作为备用
+pub fn write_pdf(path: &Path, pages: &[Page], options: &RenderOptions) -> Result<()> {
+    let mut doc = PdfDocument::new("TOTP 备份");
+    let mut pdf_pages = Vec::new();
+    for page in pages {
+        pdf_pages.push(PdfPage::new(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), page_ops(page, options)?));
+    }
+    let mut warnings = Vec::new();
+    let bytes = doc
+        .with_pages(pdf_pages)
+        .save(&PdfSaveOptions::default(), &mut warnings);
+    fs::write(path, bytes)
+        .with_context(|| format!("写入 PDF 失败: {}", path.display()))
+}
+
+/// 单页的绘制指令
+fn page_ops(page: &Page, options: &RenderOptions) -> Result<Vec<Op>> {
+    let qr = render::encode(&page.url, options.ecc)
+        .with_context(|| format!("生成二维码失败: {} ({})", page.label, page.username))?;
+
+    // 内置字体只支持拉丁字符，其余文字先转写为 ASCII
+    let mut ops = Vec::new();
+    text(&mut ops, PAGE_MARGIN, 272.0, BuiltinFont::Helvetica, 12.0, &format!("Issuer: {}", deunicode::deunicode(&page.issuer)));
+    text(&mut ops, PAGE_MARGIN, 260.0, BuiltinFont::HelveticaBold, 22.0, &deunicode::deunicode(&page.label));
+    text(&mut ops, PAGE_MARGIN, 250.0, BuiltinFont::Helvetica, 14.0, &deunicode::deunicode(&page.username));
+
+    // 每个深色模块画成一个矩形，矢量输出打印时不会模糊
+    let modules = qr.size() as u32 + options.margin * 2;
+    let module = Mm(QR_SIZE / modules as f32).into_pt();
+    let left = Mm((PAGE_WIDTH - QR_SIZE) / 2.0).into_pt();
+    let top = Mm(240.0).into_pt();
+    let mut rings = Vec::new();
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if !qr.get_module(x, y) {
+                continue;
+            }
+            let px = left.0 + (x as u32 + options.margin) as f32 * module.0;
+            let py = top.0 - (y as u32 + options.margin + 1) as f32 * module.0;
+            rings.extend(Rect::from_xywh(Pt(px), Pt(py), module, module).to_polygon().rings);
+        }
+    }
+    ops.push(Op::DrawPolygon {
+        polygon: Polygon {
+            rings,
+            mode: PaintMode::Fill,
+            winding_order: WindingOrder::NonZero,
+        },
+    });
+
+    let mut y = 240.0 - QR_SIZE - 10.0;
+    let chars: Vec<char> = page.url.chars().collect();
+    for line in chars.chunks(URL_LINE_CHARS) {
+        text(&mut ops, PAGE_MARGIN, y, BuiltinFont::Courier, 7.0, &line.iter().collect::<String>());
+        y -= 3.5;
+    }
+    Ok(ops)
+}
+
+/// 在指定位置（毫米，原点在左下角）写一行文字
+fn text(ops: &mut Vec<Op>, x: f32, y: f32, font: BuiltinFont, size: f32, content: &str) {
+    ops.extend([
+        Op::StartTextSection,
+        Op::SetTextCursor { pos: Point::new(Mm(x), Mm(y)) },
+        Op::SetFont { font: PdfFontHandle::Builtin(font), size: Pt(size) },
+        Op::ShowText { items: vec![TextItem::Text(content.to_string())] },
+        Op::EndTextSection,
+    ]);
+}
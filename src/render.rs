@@ -0,0 +1,366 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use image::{codecs::{jpeg::JpegEncoder, webp::WebPEncoder}, imageops, DynamicImage, GrayImage, ImageFormat, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::{fmt, io::Cursor, str::FromStr};
+
+/// 默认四周留白宽度（模块数），与 totp-rs 内置 PNG 渲染保持一致
+pub const DEFAULT_MARGIN: u32 = 4;
+/// 默认每个模块的像素边长，与 totp-rs 内置 PNG 渲染保持一致
+pub const DEFAULT_SCALE: u32 = 8;
+/// 默认 JPEG 输出质量
+pub const DEFAULT_JPEG_QUALITY: u8 = 95;
+/// 低于该 JPEG 质量时提示可能影响扫码
+pub const MIN_JPEG_QUALITY: u8 = 80;
+
+/// 二维码纠错等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Ecc {
+    /// 约 7% 容错，码最小
+    L,
+    /// 约 15% 容错（默认，与 totp-rs 一致）
+    M,
+    /// 约 25% 容错
+    Q,
+    /// 约 30% 容错，适合打印备份
+    H,
+}
+
+impl Ecc {
+    /// 版本 40 二维码在该纠错等级下以字节模式最多能容纳的字节数
+    pub fn max_bytes(self) -> usize {
+        gen_totp_pic::qr_max_bytes(self.level())
+    }
+
+    fn level(self) -> QrCodeEcc {
+        match self {
+            Ecc::L => QrCodeEcc::Low,
+            Ecc::M => QrCodeEcc::Medium,
+            Ecc::Q => QrCodeEcc::Quartile,
+            Ecc::H => QrCodeEcc::High,
+        }
+    }
+}
+
+/// 中心 logo 的最大边长占二维码码区的比例
+const LOGO_FRACTION: f64 = 0.2;
+
+/// 低于该对比度时多数扫码器难以识别
+pub const MIN_CONTRAST: f64 = 3.0;
+
+/// `#RRGGBB` 形式的颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub [u8; 3]);
+
+impl Color {
+    pub const BLACK: Color = Color([0, 0, 0]);
+    pub const WHITE: Color = Color([255, 255, 255]);
+
+    /// WCAG 定义的相对亮度
+    fn luminance(self) -> f64 {
+        let channel = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        let [r, g, b] = self.0;
+        0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+    }
+
+    /// 两种颜色的对比度（1 ~ 21）
+    pub fn contrast(self, other: Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("颜色格式应为 #RRGGBB: {}", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("已校验为十六进制");
+        Ok(Color([channel(0), channel(2), channel(4)]))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{:02X}{:02X}{:02X}", r, g, b)
+    }
+}
+
+/// 二维码渲染参数
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// 每个模块的像素边长
+    pub scale: u32,
+    /// 纠错等级
+    pub ecc: Ecc,
+    /// 四周留白宽度（模块数）
+    pub margin: u32,
+    /// 模块颜色
+    pub fg: Color,
+    /// 背景颜色
+    pub bg: Color,
+    /// 叠加在二维码中心的 logo
+    pub logo: Option<RgbaImage>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            scale: DEFAULT_SCALE,
+            ecc: Ecc::M,
+            margin: DEFAULT_MARGIN,
+            fg: Color::BLACK,
+            bg: Color::WHITE,
+            logo: None,
+        }
+    }
+}
+
+/// 按指定纠错等级将文本编码为二维码，自动选用能容纳内容的最小版本
+///
+/// 超出版本 40 的容量时说明链接长度与上限，并提示可用的更低纠错等级
+pub fn encode(url: &str, ecc: Ecc) -> Result<QrCode> {
+    QrCode::encode_text(url, ecc.level()).map_err(|_| {
+        let hint = match [Ecc::L, Ecc::M, Ecc::Q].into_iter().rev().find(|lower| *lower < ecc && url.len() <= lower.max_bytes()) {
+            Some(lower) => format!("请缩短服务名称 / 账户名，或改用 --ecc {}（最多 {} 字节）", format!("{:?}", lower).to_lowercase(), lower.max_bytes()),
+            None => "请缩短服务名称 / 账户名".to_string(),
+        };
+        anyhow::anyhow!(
+            "链接长度为 {} 字节，超过纠错等级 {:?} 下二维码的最大容量 {} 字节，{}",
+            url.len(), ecc, ecc.max_bytes(), hint
+        )
+    })
+}
+
+/// 将 otpauth 链接渲染为终端字符画，返回内容与占用的列数
+///
+/// Unicode 模式用上下半块字符，一个字符表示上下两个模块；与 qrencode -t UTF8 一致，
+/// 浅色模块（含留白）画成方块，适合深色背景的终端。ASCII 模式每个模块占两列，深色模块画成 ##
+pub fn render_terminal(url: &str, options: &RenderOptions, unicode: bool) -> Result<(String, usize)> {
+    let qr = encode(url, options.ecc)?;
+    let border = options.margin as i32;
+    let range = -border..qr.size() + border;
+    // get_module 在码区之外返回 false，正好对应留白
+    let light = |x: i32, y: i32| !qr.get_module(x, y);
+    let mut out = String::new();
+    if unicode {
+        for y in range.clone().step_by(2) {
+            for x in range.clone() {
+                let bottom = range.contains(&(y + 1)) && light(x, y + 1);
+                out.push(match (light(x, y), bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push('\n');
+        }
+    } else {
+        for y in range.clone() {
+            for x in range.clone() {
+                out.push_str(if light(x, y) { "  " } else { "##" });
+            }
+            out.push('\n');
+        }
+    }
+    let columns = range.len() * if unicode { 1 } else { 2 };
+    Ok((out, columns))
+}
+
+/// 根据 LC_ALL / LC_CTYPE / LANG 判断终端能否显示 Unicode 方块字符
+pub fn terminal_supports_unicode() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+/// 将 otpauth 链接渲染为黑白灰度图像，模块按整数倍放大，保证边缘清晰
+///
+/// 拼图等打印场景使用该函数，不应用自定义颜色
+pub fn render_canvas(url: &str, options: &RenderOptions) -> Result<GrayImage> {
+    let qr = encode(url, options.ecc)?;
+    let scale = options.scale;
+    let border = options.margin;
+    let size = (qr.size() as u32 + border * 2) * scale;
+    let mut canvas = GrayImage::from_pixel(size, size, Luma([255]));
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if !qr.get_module(x, y) {
+                continue;
+            }
+            let x0 = (x as u32 + border) * scale;
+            let y0 = (y as u32 + border) * scale;
+            for py in y0..y0 + scale {
+                for px in x0..x0 + scale {
+                    canvas.put_pixel(px, py, Luma([0]));
+                }
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+/// 将 otpauth 链接渲染为图像，默认黑白时保持灰度，否则按前景/背景色着色
+pub fn render_image(url: &str, options: &RenderOptions) -> Result<DynamicImage> {
+    let canvas = render_canvas(url, options)?;
+    if options.fg == Color::BLACK && options.bg == Color::WHITE && options.logo.is_none() {
+        return Ok(DynamicImage::ImageLuma8(canvas));
+    }
+    let mut colored = RgbImage::from_fn(canvas.width(), canvas.height(), |x, y| {
+        let color = if canvas.get_pixel(x, y).0[0] == 0 { options.fg } else { options.bg };
+        Rgb(color.0)
+    });
+    if let Some(logo) = &options.logo {
+        overlay_logo(&mut colored, logo, options);
+    }
+    Ok(DynamicImage::ImageRgb8(colored))
+}
+
+/// 将 logo 缩放到码区的 LOGO_FRACTION 以内并居中叠加，四周垫一个模块宽的背景色
+fn overlay_logo(image: &mut RgbImage, logo: &RgbaImage, options: &RenderOptions) {
+    let code_pixels = image.width() - options.margin * 2 * options.scale;
+    let max_side = ((code_pixels as f64 * LOGO_FRACTION) as u32).max(1);
+    let logo = imageops::resize(
+        logo,
+        (logo.width() * max_side / logo.width().max(logo.height())).max(1),
+        (logo.height() * max_side / logo.width().max(logo.height())).max(1),
+        imageops::FilterType::Lanczos3,
+    );
+
+    let pad = options.scale;
+    let mut badge = RgbaImage::from_pixel(
+        logo.width() + pad * 2,
+        logo.height() + pad * 2,
+        Rgba([options.bg.0[0], options.bg.0[1], options.bg.0[2], 255]),
+    );
+    imageops::overlay(&mut badge, &logo, pad as i64, pad as i64);
+
+    let x = (image.width() - badge.width()) / 2;
+    let y = (image.height() - badge.height()) / 2;
+    let badge = DynamicImage::ImageRgba8(badge).to_rgb8();
+    imageops::replace(image, &badge, x as i64, y as i64);
+}
+
+/// 将 otpauth 链接渲染为 PNG
+pub fn render_png(url: &str, options: &RenderOptions) -> Result<Vec<u8>> {
+    encode_png(&render_image(url, options)?)
+}
+
+/// 将已渲染的图像编码为 PNG
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png)
+        .context("PNG 编码失败")?;
+    Ok(png.into_inner())
+}
+
+/// 将已渲染的图像编码为尽量小的 PNG：只有两种颜色时写成 1 位调色板图像，否则以最高压缩级别编码
+///
+/// 解码后的像素与 [`encode_png`] 完全一致
+pub fn encode_png_optimized(image: &DynamicImage) -> Result<Vec<u8>> {
+    let rgb = image.to_rgb8();
+    let mut palette: Vec<[u8; 3]> = Vec::with_capacity(2);
+    for pixel in rgb.pixels() {
+        if !palette.contains(&pixel.0) {
+            if palette.len() == 2 {
+                return encode_png_with(&rgb, png::ColorType::Rgb, png::BitDepth::Eight, None, rgb.as_raw());
+            }
+            palette.push(pixel.0);
+        }
+    }
+
+    // 每行按字节对齐，高位在左
+    let row_bytes = rgb.width().div_ceil(8) as usize;
+    let mut packed = vec![0u8; row_bytes * rgb.height() as usize];
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        if pixel.0 != palette[0] {
+            packed[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    let palette = palette.concat();
+    encode_png_with(&rgb, png::ColorType::Indexed, png::BitDepth::One, Some(palette), &packed)
+}
+
+fn encode_png_with(image: &RgbImage, color: png::ColorType, depth: png::BitDepth, palette: Option<Vec<u8>>, data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width(), image.height());
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    if let Some(palette) = palette {
+        encoder.set_palette(palette);
+    }
+    encoder.set_compression(png::Compression::Best);
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    let mut writer = encoder.write_header().context("PNG 编码失败")?;
+    writer.write_image_data(data).context("PNG 编码失败")?;
+    writer.finish().context("PNG 编码失败")?;
+    Ok(out)
+}
+
+/// 将已渲染的图像编码为 WebP，使用无损压缩保证模块边缘清晰
+pub fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut webp = Vec::new();
+    image.write_with_encoder(WebPEncoder::new_lossless(&mut webp))
+        .context("WebP 编码失败")?;
+    Ok(webp)
+}
+
+/// 将已渲染的图像编码为 JPEG，quality 取值 1-100
+pub fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    let mut jpeg = Vec::new();
+    image.write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, quality))
+        .context("JPEG 编码失败")?;
+    Ok(jpeg)
+}
+
+/// 将 otpauth 链接编码为 SVG 二维码，宽高按模块像素换算
+pub fn render_svg(url: &str, options: &RenderOptions) -> Result<String> {
+    let qr = encode(url, options.ecc)?;
+
+    let border = options.margin;
+    let size = qr.size() as u32 + border * 2;
+    let pixels = size * options.scale;
+    let mut path = String::new();
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                if !path.is_empty() {
+                    path.push(' ');
+                }
+                path.push_str(&format!("M{},{}h1v1h-1z", x as u32 + border, y as u32 + border));
+            }
+        }
+    }
+
+    Ok(format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" ",
+            "width=\"{pixels}\" height=\"{pixels}\" viewBox=\"0 0 {size} {size}\" stroke=\"none\">\n",
+            "\t<rect width=\"100%\" height=\"100%\" fill=\"{bg}\"/>\n",
+            "\t<path d=\"{path}\" fill=\"{fg}\"/>\n",
+            "</svg>\n"
+        ),
+        size = size,
+        pixels = pixels,
+        bg = options.bg,
+        fg = options.fg,
+        path = path
+    ))
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use log::warn;
+use std::{fs, path::{self, Component, Path, PathBuf}};
+
+use gen_totp_pic::{is_secret_reference, redact, TotpEntry, FILE_SECRET_PREFIX};
+
+/// 单个条目的处理结果
+#[derive(Debug, Serialize)]
+pub struct EntryReport {
+    pub index: usize,
+    pub label: String,
+    pub username: String,
+    pub algorithm: String,
+    pub digits: u32,
+    pub period: u64,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// 同时输出多种格式时，除 image_path 外的其余图片
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub other_images: Vec<ImageReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 单个图片文件的路径与摘要
+#[derive(Debug, Serialize)]
+pub struct ImageReport {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// 处理结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    Failed,
+}
+
+/// 计算内容的 SHA-256 十六进制摘要
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 将所有条目的处理结果写入 JSON 报告
+pub fn write_report(path: &Path, entries: &[EntryReport]) -> Result<()> {
+    let json = serde_json::to_string_pretty(entries)
+        .context("序列化报告失败")?;
+    fs::write(path, json)
+        .with_context(|| format!("写入报告失败: {}", path.display()))
+}
+
+/// --rejects 中的失败条目：保留条目的原始字段，并附上失败原因
+#[derive(Debug, Serialize)]
+pub struct Reject {
+    #[serde(flatten)]
+    pub entry: serde_json::Map<String, serde_json::Value>,
+    pub error: String,
+}
+
+impl Reject {
+    /// 在 --force-* 覆盖参数之前记下条目的字段；未指定 --show-secrets 时遮蔽 secret，
+    /// env: / file: 引用本身不含密钥，原样保留
+    pub fn snapshot(entry: &TotpEntry) -> serde_json::Map<String, serde_json::Value> {
+        let serde_json::Value::Object(mut fields) = serde_json::to_value(entry).expect("条目可以序列化") else {
+            unreachable!("条目总是序列化为对象");
+        };
+        if !is_secret_reference(&entry.secret) {
+            fields.insert("secret".to_string(), redact(&entry.secret).into());
+        }
+        fields
+    }
+}
+
+/// 将失败条目写入 JSON，结构与输入文件相同（外层为 entries），修正后可直接作为输入重新运行
+///
+/// 读取时 file: 引用的相对路径已换算为相对当前目录，这里再改写为相对失败条目文件所在目录，
+/// 与重新运行时的解析方式一致
+pub fn write_rejects(path: &Path, rejects: &[Reject]) -> Result<()> {
+    let base = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut outside = 0;
+    let mut entries = Vec::new();
+    for reject in rejects {
+        let mut value = serde_json::to_value(reject).context("序列化失败条目失败")?;
+        if let Some(target) = value["secret"].as_str().and_then(|secret| secret.strip_prefix(FILE_SECRET_PREFIX))
+            && Path::new(target).is_relative()
+        {
+            let relative = relative_to(Path::new(target), base)?;
+            if relative.starts_with(Component::ParentDir) {
+                outside += 1;
+            }
+            value["secret"] = format!("{}{}", FILE_SECRET_PREFIX, relative.display()).into();
+        }
+        entries.push(value);
+    }
+    if outside > 0 {
+        warn!("⚠️  {} 个失败条目引用的 secret 文件不在 {} 目录内，重新运行时需指定 --allow-absolute-secrets", outside, base.display());
+    }
+    let json = serde_json::to_string_pretty(&serde_json::json!({ "entries": entries }))
+        .context("序列化失败条目失败")?;
+    fs::write(path, json)
+        .with_context(|| format!("写入失败条目失败: {}", path.display()))
+}
+
+/// 计算 target 相对于 base 目录的路径，两者都可以是相对当前目录的路径
+fn relative_to(target: &Path, base: &Path) -> Result<PathBuf> {
+    let target = path::absolute(target).with_context(|| format!("无法解析路径: {}", target.display()))?;
+    let base = path::absolute(base).with_context(|| format!("无法解析路径: {}", base.display()))?;
+    let common = target.components().zip(base.components()).take_while(|(a, b)| a == b).count();
+    let mut relative: PathBuf = base.components().skip(common).map(|_| Component::ParentDir).collect();
+    relative.extend(target.components().skip(common));
+    Ok(relative)
+}
+
+/// SHA256SUMS 文件名
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// 在 dir 下写入与 sha256sum 兼容的 SHA256SUMS，路径相对于 dir，可用 `sha256sum -c SHA256SUMS` 校验
+pub fn write_checksums(dir: &Path, files: &[PathBuf]) -> Result<PathBuf> {
+    let mut content = String::new();
+    for file in files {
+        let bytes = fs::read(file)
+            .with_context(|| format!("读取文件失败: {}", file.display()))?;
+        let name = file.strip_prefix(dir).unwrap_or(file);
+        let name = name.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        content.push_str(&format!("{}  {}\n", sha256_hex(&bytes), name));
+    }
+    let path = dir.join(CHECKSUMS_FILE);
+    fs::write(&path, content)
+        .with_context(|| format!("写入校验文件失败: {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,179 @@
+//! --tui：在终端中浏览条目、搜索并勾选需要生成的条目，界面中不显示 secret
+
+use anyhow::{Context, Result};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::io::{self, IsTerminal};
+
+use gen_totp_pic::TotpEntry;
+
+/// 翻页时移动的行数
+const PAGE: isize = 10;
+
+/// 显示条目列表供用户勾选，返回勾选条目的序号；用户取消时返回 None
+pub fn select(entries: &[TotpEntry]) -> Result<Option<Vec<usize>>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(anyhow::anyhow!("--tui 需要在交互式终端中运行"));
+    }
+    let mut terminal = ratatui::try_init().context("无法初始化终端界面")?;
+    let result = App::new(entries).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// 界面状态
+struct App<'a> {
+    entries: &'a [TotpEntry],
+    /// 每个条目是否勾选，默认全部勾选
+    checked: Vec<bool>,
+    query: String,
+    /// 正在输入搜索词
+    searching: bool,
+    /// 匹配搜索词的条目序号
+    visible: Vec<usize>,
+    list: ListState,
+}
+
+impl<'a> App<'a> {
+    fn new(entries: &'a [TotpEntry]) -> Self {
+        App {
+            entries,
+            checked: vec![true; entries.len()],
+            query: String::new(),
+            searching: false,
+            visible: (0..entries.len()).collect(),
+            list: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    /// 处理按键直到用户确认（Enter）或取消（q / Esc / Ctrl-C）
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<Option<Vec<usize>>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame)).context("无法绘制终端界面")?;
+            let Event::Key(key) = event::read().context("无法读取按键")? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(None);
+            }
+            if self.searching {
+                self.search_key(key);
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => {
+                    let selected = (0..self.entries.len()).filter(|&index| self.checked[index]).collect();
+                    return Ok(Some(selected));
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::PageUp => self.move_by(-PAGE),
+                KeyCode::PageDown => self.move_by(PAGE),
+                KeyCode::Char(' ') => self.toggle_current(),
+                KeyCode::Char('a') => self.toggle_visible(),
+                KeyCode::Char('/') => self.searching = true,
+                _ => {}
+            }
+        }
+    }
+
+    /// 搜索模式下的按键：Enter 保留搜索词，Esc 清空搜索词
+    fn search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Esc => {
+                self.searching = false;
+                self.query.clear();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Char(c) => self.query.push(c),
+            _ => return,
+        }
+        self.refilter();
+    }
+
+    /// 按搜索词（不区分大小写）匹配服务名称、issuer 和账户名
+    fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        self.visible = self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                [entry.label_name.as_str(), entry.issuer(), entry.username.as_str()]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&query))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.list.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if let Some(current) = self.list.selected() {
+            let last = self.visible.len().saturating_sub(1) as isize;
+            self.list.select(Some((current as isize + delta).clamp(0, last) as usize));
+        }
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some(&index) = self.list.selected().and_then(|current| self.visible.get(current)) {
+            self.checked[index] = !self.checked[index];
+        }
+    }
+
+    /// 当前可见的条目全部勾选时全部取消，否则全部勾选
+    fn toggle_visible(&mut self) {
+        let all = self.visible.iter().all(|&index| self.checked[index]);
+        for &index in &self.visible {
+            self.checked[index] = !all;
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search, list, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let cursor = if self.searching { "_" } else { "" };
+        let search_box = Paragraph::new(format!("{}{}", self.query, cursor))
+            .block(Block::bordered().title(" 搜索（/ 开始输入，Enter 确定，Esc 清空） "));
+        frame.render_widget(search_box, search);
+
+        let items: Vec<ListItem> = self.visible
+            .iter()
+            .map(|&index| {
+                let entry = &self.entries[index];
+                let mark = if self.checked[index] { "[x]" } else { "[ ]" };
+                let issuer = match entry.issuer.as_deref() {
+                    Some(issuer) if issuer != entry.label_name => format!("  [{}]", issuer),
+                    _ => String::new(),
+                };
+                ListItem::new(format!("{} {:>3}. {} ({}){}", mark, index + 1, entry.label_name, entry.username, issuer))
+            })
+            .collect();
+        let total = self.checked.iter().filter(|&&checked| checked).count();
+        let title = format!(" 已勾选 {}/{}，显示 {} 项 ", total, self.entries.len(), self.visible.len());
+        let entries = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(entries, list, &mut self.list);
+
+        let keys = Line::from(" ↑/↓ 移动  PgUp/PgDn 翻页  空格 勾选  a 全选/全不选  / 搜索  Enter 生成  q 取消");
+        frame.render_widget(keys, help);
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use gen_totp_pic::redact_url;
+use image::{imageops, GrayImage};
+
+use crate::render::{self, RenderOptions};
+
+/// --scan-test 渲染时每个模块的像素边长，模拟打印得很小的二维码
+pub const SCAN_TEST_SCALE: u32 = 2;
+
+/// 解码图片中的二维码，确认内容与预期的 otpauth 链接一致
+pub fn verify_image(bytes: &[u8], expected: &str) -> Result<()> {
+    let image = image::load_from_memory(bytes)
+        .context("无法读取图片")?
+        .to_luma8();
+    check(image, expected)
+}
+
+/// 以 SCAN_TEST_SCALE 重新渲染（保留颜色与 logo）并解码，无法识别时返回错误；
+/// 再以每模块 1 像素渲染，此时无法识别返回 Ok(false)，表示尺寸余量不足
+pub fn scan_test(url: &str, options: &RenderOptions) -> Result<bool> {
+    let render = |scale| render::render_image(url, &RenderOptions { scale, ..options.clone() });
+    check(render(SCAN_TEST_SCALE)?.to_luma8(), url)
+        .with_context(|| format!("缩小到每模块 {} 像素后无法识别", SCAN_TEST_SCALE))?;
+    Ok(check(render(1)?.to_luma8(), url).is_ok())
+}
+
+/// 识别灰度图并与预期内容比对
+fn check(mut image: GrayImage, expected: &str) -> Result<()> {
+    // 浅色模块配深色背景时，反色后再识别一次
+    let decoded = decode(&image).or_else(|_| {
+        imageops::invert(&mut image);
+        decode(&image)
+    })?;
+    if decoded != expected {
+        return Err(anyhow::anyhow!(
+            "二维码内容不一致\n  期望: {}\n  实际: {}",
+            redact_url(expected),
+            redact_url(&decoded)
+        ));
+    }
+    Ok(())
+}
+
+/// 识别灰度图中的第一个二维码并返回其文本内容
+fn decode(image: &GrayImage) -> Result<String> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    let grid = prepared
+        .detect_grids()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("图片中未识别到二维码"))?;
+    let (_, content) = grid
+        .decode()
+        .map_err(|e| anyhow::anyhow!("二维码解码失败: {:?}", e))?;
+    Ok(content)
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use log::{error, info};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use crate::{cli::Cli, generate, input};
+
+/// 连续写入时等待文件稳定的时间
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 监听线程发往主循环的消息
+enum Signal {
+    Changed,
+    Stop,
+}
+
+/// 先生成一次，之后每当输入文件变化就重新生成，直到按下 Ctrl-C
+pub fn watch(cli: &Cli) -> Result<()> {
+    if cli.source.input.iter().any(|path| path.as_os_str() == "-") {
+        return Err(anyhow::anyhow!("--watch 不支持从标准输入读取"));
+    }
+    if cli.source.input.iter().any(|path| input::is_url(path)) {
+        return Err(anyhow::anyhow!("--watch 不支持 URL 输入"));
+    }
+    let inputs: Vec<PathBuf> = input::expand_inputs(&cli.source.input)?
+        .iter()
+        .map(|path| absolute(path))
+        .collect::<Result<_>>()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let stop = sender.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(Signal::Stop);
+    })
+    .context("无法注册 Ctrl-C 处理")?;
+
+    // 监听所在目录而不是文件本身：很多编辑器保存时会先写临时文件再重命名
+    let targets = inputs.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // 只关心写入、创建、重命名，生成时读取输入产生的访问事件不算
+        if let Ok(event) = event
+            && (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
+            && event.paths.iter().any(|path| targets.contains(path))
+        {
+            let _ = sender.send(Signal::Changed);
+        }
+    })
+    .context("无法创建文件监听")?;
+    for input in &inputs {
+        let dir = input.parent().unwrap_or(Path::new("."));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("无法监听目录: {}", dir.display()))?;
+    }
+
+    regenerate(cli);
+    info!("👀 正在监听输入文件变化，按 Ctrl-C 退出");
+    // 生成期间又收到变化通知时为 true，生成结束后立即再生成一次
+    let mut pending = false;
+    loop {
+        if !pending {
+            match receiver.recv() {
+                Ok(Signal::Changed) => {}
+                Ok(Signal::Stop) | Err(_) => break,
+            }
+        }
+        // 合并短时间内的连续写入，只重新生成一次
+        loop {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(Signal::Changed) => continue,
+                Ok(Signal::Stop) => {
+                    info!("👋 已停止监听");
+                    return Ok(());
+                }
+                Err(_) => break,
+            }
+        }
+        info!("🔁 [{}] 检测到输入文件变化，重新生成", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+        regenerate(cli);
+        // 生成期间积压的变化通知合并为一次重新生成，退出请求立即生效
+        pending = false;
+        while let Ok(signal) = receiver.try_recv() {
+            match signal {
+                Signal::Changed => pending = true,
+                Signal::Stop => {
+                    info!("👋 已停止监听");
+                    return Ok(());
+                }
+            }
+        }
+    }
+    info!("👋 已停止监听");
+    Ok(())
+}
+
+/// 执行一次完整生成，失败时只记录错误并继续监听
+fn regenerate(cli: &Cli) {
+    if let Err(e) = generate::run(cli) {
+        error!("❌ {:#}", e);
+    }
+}
+
+/// 转换为绝对路径，与监听事件中的路径保持一致
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("无法访问目录: {}", dir.display()))?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("无效的输入路径: {}", path.display()))?;
+    Ok(dir.join(name))
+}
@@ -0,0 +1,150 @@
+//! 端到端测试：读取 tests/fixtures/sample.json，在临时目录中生成真实的二维码文件
+
+use assert_cmd::Command;
+use std::{fs, path::Path};
+
+const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sample.json");
+const DUPLICATES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/duplicates.json");
+
+/// 在临时目录中运行命令行，默认的 qr/ 等相对路径都落在该目录下
+fn run_in(dir: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    Command::cargo_bin("gen-totp-pic")
+        .expect("找不到 gen-totp-pic 可执行文件")
+        .current_dir(dir)
+        .args(args)
+        .assert()
+}
+
+/// 解码 PNG 中的二维码内容
+fn decode_png(path: &Path) -> String {
+    let image = image::open(path).expect("无法读取 PNG").to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    let grids = prepared.detect_grids();
+    assert_eq!(grids.len(), 1, "应恰好识别到一个二维码: {}", path.display());
+    grids[0].decode().expect("二维码解码失败").1
+}
+
+#[test]
+fn generates_png_and_links_from_sample() {
+    let dir = tempfile::tempdir().unwrap();
+    run_in(dir.path(), &[SAMPLE]).success();
+
+    for name in ["GitHub-aliceexamplecom", "Google-bob"] {
+        let png = dir.path().join("qr").join(format!("{}.png", name));
+        assert!(fs::metadata(&png).unwrap().len() > 0, "PNG 为空: {}", png.display());
+        let link = fs::read_to_string(dir.path().join("qr/links").join(format!("{}.txt", name))).unwrap();
+        assert!(link.starts_with("otpauth://totp/"));
+        assert_eq!(decode_png(&png), link);
+    }
+}
+
+#[test]
+fn dry_run_writes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    run_in(dir.path(), &["--dry-run", SAMPLE]).success();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn missing_input_exits_with_io_code() {
+    let dir = tempfile::tempdir().unwrap();
+    run_in(dir.path(), &["missing.json"]).code(3);
+}
+
+#[test]
+fn group_by_issuer_suffixes_colliding_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    run_in(dir.path(), &["--group-by", "issuer", DUPLICATES]).success();
+
+    let qr = dir.path().join("qr");
+    // 同一分组内的重名条目追加后缀，其他分组中的同名文件不受影响
+    for path in ["GitHub/GitHub-alice.png", "GitHub/GitHub-alice-1.png", "GitHubEnterprise/GitHub-alice.png"] {
+        assert!(qr.join(path).is_file(), "缺少 {}", path);
+    }
+    assert_eq!(fs::read_dir(qr.join("GitHub")).unwrap().count(), 2);
+}
+
+#[test]
+fn reproducible_output_ignores_input_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut export: serde_json::Value = serde_json::from_str(&fs::read_to_string(DUPLICATES).unwrap()).unwrap();
+    export["entries"].as_array_mut().unwrap().reverse();
+    let reversed = dir.path().join("reversed.json");
+    fs::write(&reversed, export.to_string()).unwrap();
+
+    for (input, output) in [(DUPLICATES, "a"), (reversed.to_str().unwrap(), "b")] {
+        run_in(dir.path(), &["--reproducible", "--format", "png,uri", "--output-dir", output, input]).success();
+    }
+    let list = |sub: &str| -> Vec<_> {
+        fs::read_dir(dir.path().join("a").join(sub))
+            .unwrap()
+            .map(|file| Path::new(sub).join(file.unwrap().file_name()))
+            .filter(|name| dir.path().join("a").join(name).is_file())
+            .collect()
+    };
+    let names: Vec<_> = list("").into_iter().chain(list("links")).collect();
+    assert_eq!(names.len(), 7);
+    for name in names {
+        let a = fs::read(dir.path().join("a").join(&name)).unwrap();
+        let b = fs::read(dir.path().join("b").join(&name)).unwrap();
+        assert_eq!(a, b, "{:?} 内容不一致", name);
+    }
+}
+
+#[test]
+fn strict_checks_apply_to_migration_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("short.json");
+    let short = r#"{"username":"a","label_name":"Short","secret":"JBSWY3DP","algorithm":"SHA1","digits":6,"period_time":30}"#;
+    let valid = r#"{"username":"b","label_name":"Valid","secret":"JBSWY3DPEHPK3PXPJBSWY3DP","algorithm":"SHA1","digits":6,"period_time":30}"#;
+    fs::write(&input, format!(r#"{{"entries":[{},{}]}}"#, short, valid)).unwrap();
+
+    // 过短的条目记为失败，其余条目照常导出
+    let assert = run_in(dir.path(), &["--strict", "--format", "migration", input.to_str().unwrap()]).code(5);
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("secret 过短: Short (a)"));
+    assert!(dir.path().join("qr/migration-1.png").is_file());
+}
+
+#[test]
+fn rejects_file_can_be_fixed_and_rerun() {
+    let dir = tempfile::tempdir().unwrap();
+    let backup = dir.path().join("backup");
+    fs::create_dir(&backup).unwrap();
+    fs::create_dir(dir.path().join("out")).unwrap();
+    let entry = r#"{"username":"alice","label_name":"GitHub","secret":"file:keys/github.txt","algorithm":"SHA1","digits":6,"period_time":30}"#;
+    fs::write(backup.join("export.json"), format!(r#"{{"entries":[{}]}}"#, entry)).unwrap();
+
+    // secret 文件尚不存在，条目失败并写入另一个目录下的 rejects.json
+    run_in(dir.path(), &["--force-digits", "8", "--rejects", "out/rejects.json", "backup/export.json"]).code(5);
+    let rejects: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.path().join("out/rejects.json")).unwrap()).unwrap();
+    let reject = &rejects["entries"][0];
+    // file: 引用改写为相对 rejects.json 所在目录，参数保留输入中的原值
+    assert_eq!(reject["secret"], "file:../backup/keys/github.txt");
+    assert_eq!(reject["digits"], 6);
+    assert!(reject["error"].is_string());
+
+    // 补上 secret 文件后，在 rejects.json 所在目录直接重新运行
+    fs::create_dir(backup.join("keys")).unwrap();
+    fs::write(backup.join("keys/github.txt"), "JBSWY3DPEHPK3PXPJBSWY3DP\n").unwrap();
+    run_in(&dir.path().join("out"), &["--allow-absolute-secrets", "rejects.json"]).success();
+    assert!(dir.path().join("out/qr/GitHub-alice.png").is_file());
+}
+
+#[test]
+fn zip_remove_loose_packs_link_files() {
+    let dir = tempfile::tempdir().unwrap();
+    run_in(dir.path(), &["--format", "png,uri", "--zip", "backup.zip", "--password", "hunter2", "--remove-loose", SAMPLE]).success();
+
+    let archive = zip::ZipArchive::new(fs::File::open(dir.path().join("backup.zip")).unwrap()).unwrap();
+    let names: Vec<String> = archive.file_names().map(|name| name.unwrap().into_owned()).collect();
+    for name in ["GitHub-aliceexamplecom.png", "links/GitHub-aliceexamplecom.txt", "uris.txt"] {
+        assert!(names.iter().any(|n| n == name), "压缩包缺少 {}: {:?}", name, names);
+    }
+    // 含 secret 的明文文件不应留在磁盘上
+    assert!(!dir.path().join("qr/uris.txt").exists());
+    assert_eq!(fs::read_dir(dir.path().join("qr/links")).unwrap().count(), 0);
+}